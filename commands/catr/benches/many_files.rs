@@ -0,0 +1,51 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+const FILES: usize = 2000;
+
+// --------------------------------------------------
+/// Write many small files, like the test fixtures, to a scratch directory.
+fn gen_files() -> Vec<PathBuf> {
+    let dir =
+        std::env::temp_dir().join(format!("catr-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    (0..FILES)
+        .map(|i| {
+            let path = dir.join(format!("{:05}.txt", i));
+            fs::write(&path, format!("file {}\nThe quick brown fox\n", i))
+                .unwrap();
+            path
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+fn many_small_files(c: &mut Criterion) {
+    let files = gen_files();
+    let mut group = c.benchmark_group("many_small_files");
+    group.sample_size(20);
+    for jobs in [1, 2, 4, 8] {
+        group.bench_with_input(
+            BenchmarkId::new("jobs", jobs),
+            &jobs,
+            |b, jobs| {
+                b.iter(|| {
+                    let status = Command::new(env!("CARGO_BIN_EXE_catr"))
+                        .arg(format!("--jobs={}", jobs))
+                        .args(&files)
+                        .stdout(Stdio::null())
+                        .status()
+                        .unwrap();
+                    assert!(status.success());
+                })
+            },
+        );
+    }
+    group.finish();
+    fs::remove_dir_all(files[0].parent().unwrap()).unwrap();
+}
+
+criterion_group!(benches, many_small_files);
+criterion_main!(benches);
@@ -13,6 +13,18 @@ SPIDERS="$ROOT/spiders.txt"
 BUSTLE="$ROOT/the-bustle.txt"
 ALL="$EMPTY $FOX $SPIDERS $BUSTLE"
 
+gzip  -nc $FOX     > $FOX.gz
+xz    -c  $SPIDERS > $SPIDERS.xz
+zstd  -qc $BUSTLE  > $BUSTLE.zst
+bzip2 -c  $BUSTLE  > $BUSTLE.bz2
+gzip  -nc $BUSTLE  > $BUSTLE.gz
+
+SPRING="$ROOT/spring.txt"
+CAFE="$ROOT/cafe.txt"
+iconv -f utf-8 -t gbk    $SPRING > $ROOT/spring.gbk.txt
+iconv -f utf-8 -t utf-16 $BUSTLE > $ROOT/the-bustle.utf16.txt
+iconv -f utf-8 -t latin1 $CAFE   > $ROOT/cafe.latin1.txt
+
 for FILE in $ALL; do
     BASENAME=$(basename "$FILE")
     cat    $FILE > ${OUT_DIR}/${BASENAME}.out
@@ -28,3 +40,25 @@ cat    < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).stdin.out
 cat -n < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).n.stdin.out
 cat -b < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).b.stdin.out
 
+
+cat -n $BUSTLE | sed -n 5,7p   > $OUT_DIR/$(basename $BUSTLE).lines.n.out
+head -n 2 $BUSTLE              > $OUT_DIR/$(basename $BUSTLE).head.out
+tail -n 3 $BUSTLE              > $OUT_DIR/$(basename $BUSTLE).tail.out
+cat -n $BUSTLE | tail -n 3     > $OUT_DIR/$(basename $BUSTLE).tail.n.out
+tail -c +5 $FOX | head -c 5    > $OUT_DIR/$(basename $FOX).bytes.out
+
+xxd $FOX                       > $OUT_DIR/$(basename $FOX).hex.out
+xxd $BUSTLE                    > $OUT_DIR/$(basename $BUSTLE).hex.out
+xxd -g1 -c8 -o 256 $SPIDERS    > $OUT_DIR/$(basename $SPIDERS).hex.g1.c8.o256.out
+xxd -g0 -c10 $BUSTLE           > $OUT_DIR/$(basename $BUSTLE).hex.g0.c10.out
+xxd -s 4 -l 5 $FOX             > $OUT_DIR/$(basename $FOX).hex.s4.l5.out
+
+cat -n $SPRING                 > $OUT_DIR/$(basename $SPRING).n.out
+cat    $CAFE                   > $OUT_DIR/$(basename $CAFE).out
+
+TREE="$ROOT/tree"
+tail -n +1 $(find $TREE -type f | sort) > $OUT_DIR/tree.r.H.out
+cat $(find $TREE -type f | sort)        > $OUT_DIR/tree.r.out
+
+cat -n $BUSTLE | grep -i -E -C1 'LOVE|bustle' > $OUT_DIR/$(basename $BUSTLE).grep.n.C1.out
+grep The $BUSTLE | grep -v sweeping          > $OUT_DIR/$(basename $BUSTLE).grep.exclude.out
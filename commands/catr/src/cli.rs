@@ -0,0 +1,333 @@
+use clap::{ArgGroup, CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use clap_mangen::Man;
+use std::io::{self, Write};
+
+/// First and last position of a `START:END` argument, 1-based and
+/// inclusive; no last position means up to the end.
+pub type Span = (usize, Option<usize>);
+
+/// The command line as clap sees it; `get_args` turns it into a `Config`.
+#[derive(Debug, Parser)]
+#[clap(
+    name = "catr",
+    version = "0.0.1",
+    author = "Norfloxaciner <1762161822@qq.com>",
+    about = "Rust cat",
+    group(ArgGroup::new("hex-mode").args(&["hex", "reverse"]))
+)]
+pub struct Args {
+    /// Input file(s)
+    #[clap(value_name = "FILE", default_value = "-", value_parser)]
+    pub files: Vec<String>,
+
+    /// Number lines
+    #[clap(short = 'n', long = "number", conflicts_with = "number-nonblank")]
+    pub number: bool,
+
+    /// Number non-blank lines
+    #[clap(short = 'b', long = "number-nonblank")]
+    pub number_nonblank: bool,
+
+    /// Treat all input as compressed (gzip, xz, zstd, bzip2)
+    #[clap(short = 'z', long)]
+    pub decompress: bool,
+
+    /// Highlight syntax and page output on a terminal
+    #[clap(
+        short = 'p',
+        long,
+        conflicts_with_all = &["lines", "head", "tail", "bytes"]
+    )]
+    pub pretty: bool,
+
+    /// Keep printing data appended to the files
+    #[clap(
+        short = 'f',
+        long,
+        conflicts_with_all = &[
+            "decompress",
+            "pretty",
+            "lines",
+            "head",
+            "tail",
+            "bytes",
+        ]
+    )]
+    pub follow: bool,
+
+    /// Print only this range of lines
+    #[clap(
+        long,
+        value_name = "START:END",
+        value_parser = line_range,
+        conflicts_with_all = &["head", "tail", "bytes"]
+    )]
+    pub lines: Option<Span>,
+
+    /// Print only the first N lines
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = line_count,
+        conflicts_with_all = &["tail", "bytes"]
+    )]
+    pub head: Option<usize>,
+
+    /// Print only the last N lines
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = line_count,
+        conflicts_with = "bytes"
+    )]
+    pub tail: Option<usize>,
+
+    /// Print only this range of bytes
+    #[clap(
+        long,
+        value_name = "START:END",
+        value_parser = byte_range,
+        conflicts_with_all = &["number", "number-nonblank"]
+    )]
+    pub bytes: Option<Span>,
+
+    /// Print an xxd-style hex dump
+    #[clap(
+        short = 'x',
+        long,
+        conflicts_with_all = &[
+            "number",
+            "number-nonblank",
+            "pretty",
+            "follow",
+            "lines",
+            "head",
+            "tail",
+            "reverse",
+        ]
+    )]
+    pub hex: bool,
+
+    /// Bytes per hex dump line [default: 16]
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = column_count,
+        requires = "hex"
+    )]
+    pub cols: Option<usize>,
+
+    /// Bytes per hex dump group, 0 for none [default: 2]
+    #[clap(
+        long,
+        value_name = "N",
+        value_parser = group_size,
+        requires = "hex"
+    )]
+    pub group: Option<usize>,
+
+    /// Add OFFSET to the positions of a hex dump
+    #[clap(
+        long,
+        value_name = "OFFSET",
+        value_parser = offset,
+        requires = "hex-mode"
+    )]
+    pub offset: Option<u64>,
+
+    /// Convert a hex dump back into bytes
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "number",
+            "number-nonblank",
+            "pretty",
+            "follow",
+            "lines",
+            "head",
+            "tail",
+            "bytes",
+        ]
+    )]
+    pub reverse: bool,
+
+    /// Input encoding, guessed when not given
+    #[clap(
+        long,
+        value_name = "ENCODING",
+        value_parser,
+        conflicts_with_all = &["hex", "reverse", "bytes", "follow"]
+    )]
+    pub from_encoding: Option<String>,
+
+    /// Output encoding [default: utf-8]
+    #[clap(
+        long,
+        value_name = "ENCODING",
+        value_parser,
+        conflicts_with_all = &["hex", "reverse", "bytes", "follow"]
+    )]
+    pub to_encoding: Option<String>,
+
+    /// Remove a leading UTF-8 byte order mark
+    #[clap(
+        long,
+        conflicts_with_all = &["hex", "reverse", "bytes", "follow"]
+    )]
+    pub strip_bom: bool,
+
+    /// Read the files below each directory argument
+    #[clap(short = 'r', long)]
+    pub recursive: bool,
+
+    /// Print a ==> FILE <== header before each file
+    #[clap(short = 'H', long, conflicts_with_all = &["hex", "reverse"])]
+    pub headers: bool,
+
+    /// Print only lines matching PATTERN, highlighting the matches
+    #[clap(
+        long,
+        value_name = "PATTERN",
+        value_parser,
+        conflicts_with_all = &["hex", "reverse", "bytes", "follow", "pretty"]
+    )]
+    pub grep: Option<String>,
+
+    /// Leave out lines matching PATTERN
+    #[clap(
+        long,
+        value_name = "PATTERN",
+        value_parser,
+        conflicts_with_all = &["hex", "reverse", "bytes", "follow", "pretty"]
+    )]
+    pub exclude: Option<String>,
+
+    /// Match --grep and --exclude patterns case-insensitively
+    #[clap(short = 'i', long)]
+    pub ignore_case: bool,
+
+    /// Also print N lines around each --grep match
+    #[clap(
+        short = 'C',
+        long,
+        value_name = "N",
+        value_parser = context,
+        requires = "grep"
+    )]
+    pub context: Option<usize>,
+
+    /// Read up to N files ahead in parallel [default: 1]
+    #[clap(
+        short = 'j',
+        long,
+        value_name = "N",
+        value_parser = job_count,
+        conflicts_with_all = &["follow", "hex", "reverse"]
+    )]
+    pub jobs: Option<usize>,
+
+    /// Print a completion script or the man page and exit
+    #[clap(long, value_name = "KIND", value_enum)]
+    pub generate: Option<Generate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Generate {
+    Bash,
+    Zsh,
+    Fish,
+    Man,
+}
+
+// --------------------------------------------------
+/// Write what `--generate` asked for to `out`.
+pub fn generate(kind: Generate, out: &mut impl Write) -> io::Result<()> {
+    let mut cmd = Args::command();
+    let shell = match kind {
+        Generate::Bash => Shell::Bash,
+        Generate::Zsh => Shell::Zsh,
+        Generate::Fish => Shell::Fish,
+        Generate::Man => return Man::new(cmd).render(out),
+    };
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(shell, &mut cmd, name, out);
+    Ok(())
+}
+
+// --------------------------------------------------
+fn line_range(val: &str) -> Result<Span, String> {
+    parse_span(val).map_err(|e| format!("illegal line range -- {}", e))
+}
+
+// --------------------------------------------------
+fn byte_range(val: &str) -> Result<Span, String> {
+    parse_span(val).map_err(|e| format!("illegal byte range -- {}", e))
+}
+
+// --------------------------------------------------
+fn line_count(val: &str) -> Result<usize, String> {
+    parse_count(val).map_err(|e| format!("illegal line count -- {}", e))
+}
+
+// --------------------------------------------------
+fn column_count(val: &str) -> Result<usize, String> {
+    match parse_count(val) {
+        Ok(cols) if (1..=256).contains(&cols) => Ok(cols),
+        _ => Err(format!("illegal column count -- {}", val)),
+    }
+}
+
+// --------------------------------------------------
+fn group_size(val: &str) -> Result<usize, String> {
+    parse_count(val).map_err(|e| format!("illegal group size -- {}", e))
+}
+
+// --------------------------------------------------
+fn context(val: &str) -> Result<usize, String> {
+    parse_count(val).map_err(|e| format!("illegal context -- {}", e))
+}
+
+// --------------------------------------------------
+fn job_count(val: &str) -> Result<usize, String> {
+    match parse_count(val) {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(format!("illegal job count -- {}", val)),
+    }
+}
+
+// --------------------------------------------------
+/// Parse a decimal or `0x`-prefixed hexadecimal offset.
+fn offset(val: &str) -> Result<u64, String> {
+    match val.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => val.parse(),
+    }
+    .map_err(|_| format!("illegal offset -- {}", val))
+}
+
+// --------------------------------------------------
+fn parse_count(val: &str) -> Result<usize, String> {
+    val.parse().map_err(|_| val.to_string())
+}
+
+// --------------------------------------------------
+/// Parse `START:END`, `START:`, `:END` or a single position `N`.
+fn parse_span(val: &str) -> Result<Span, String> {
+    let (first, last) = match val.split_once(':') {
+        Some((first, last)) => (first, last),
+        None => (val, val),
+    };
+    let first = match first {
+        "" => 1,
+        _ => parse_count(first).map_err(|_| val)?,
+    };
+    let last = match last {
+        "" => None,
+        _ => Some(parse_count(last).map_err(|_| val)?),
+    };
+    if first == 0 || last.is_some_and(|last| last < first) {
+        return Err(val.to_string());
+    }
+    Ok((first, last))
+}
@@ -0,0 +1,262 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{
+    Encoder, EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8,
+};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+// --------------------------------------------------
+/// Look up a WHATWG encoding label such as `gbk`, `utf-16le` or `latin1`.
+pub fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| label.to_string())
+}
+
+// --------------------------------------------------
+/// Transcode `reader` to UTF-8. Without an explicit `from` encoding it
+/// is guessed from a BOM or from the first block of input; a guess of
+/// UTF-8 is checked as the rest streams by, see [`Utf8Fallback`].
+pub fn decode(
+    mut reader: Box<dyn BufRead>,
+    from: Option<&'static Encoding>,
+    strip_bom: bool,
+) -> io::Result<Box<dyn BufRead>> {
+    let encoding = match from {
+        Some(encoding) => encoding,
+        None => detect(reader.fill_buf()?),
+    };
+    if encoding == UTF_8 {
+        if strip_bom && reader.fill_buf()?.starts_with(BOM) {
+            reader.consume(BOM.len());
+        }
+        return Ok(match from {
+            Some(_) => reader,
+            None => Box::new(BufReader::new(Utf8Fallback::new(reader))),
+        });
+    }
+
+    let decoder = DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .bom_override(true)
+        .utf8_passthru(true)
+        .strip_bom(strip_bom)
+        .build(reader);
+    Ok(Box::new(BufReader::new(decoder)))
+}
+
+// --------------------------------------------------
+/// Guess the encoding of `sample`: a BOM wins, then UTF-16 recognized by
+/// its zero bytes, then valid UTF-8, then whatever chardetng suggests
+/// (GBK, Latin-1 and friends).
+pub fn detect(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+
+    let (even, odd) = sample.iter().enumerate().filter(|(_, b)| **b == 0).fold(
+        (0, 0),
+        |(even, odd), (i, _)| {
+            if i % 2 == 0 {
+                (even + 1, odd)
+            } else {
+                (even, odd + 1)
+            }
+        },
+    );
+    let pairs = sample.len() / 2;
+    if pairs > 0 && odd * 2 > pairs && even * 10 < odd {
+        return UTF_16LE;
+    }
+    if pairs > 0 && even * 2 > pairs && odd * 10 < even {
+        return UTF_16BE;
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(sample, true);
+            detector.guess(None, true)
+        }
+    }
+}
+
+// --------------------------------------------------
+/// Passes input that was guessed to be UTF-8 through unchanged until the
+/// first invalid sequence, then guesses again from there and transcodes
+/// the rest. The guess only sees the first block, so a file with a long
+/// ASCII prefix followed by GBK would otherwise fail halfway through.
+struct Utf8Fallback {
+    inner: Box<dyn BufRead>,
+    /// Checked bytes waiting to be read, from `pos` on.
+    ready: Vec<u8>,
+    pos: usize,
+    /// The start of a character split across two blocks.
+    carry: Vec<u8>,
+    /// The transcoder for everything after the first invalid sequence.
+    decoder: Option<Box<dyn Read>>,
+}
+
+impl Utf8Fallback {
+    fn new(inner: Box<dyn BufRead>) -> Self {
+        Utf8Fallback {
+            inner,
+            ready: vec![],
+            pos: 0,
+            carry: vec![],
+            decoder: None,
+        }
+    }
+
+    // --------------------------------------------------
+    /// Check the next block of input, switching to a transcoder when it
+    /// is not UTF-8 after all.
+    fn refill(&mut self) -> io::Result<()> {
+        let mut data = std::mem::take(&mut self.ready);
+        data.clear();
+        data.append(&mut self.carry);
+        self.pos = 0;
+        let buf = self.inner.fill_buf()?;
+        let len = buf.len();
+        data.extend_from_slice(buf);
+        self.inner.consume(len);
+        if len == 0 {
+            // A character cut off by the end of input stays as it is.
+            self.ready = data;
+            return Ok(());
+        }
+
+        match std::str::from_utf8(&data) {
+            Ok(_) => {}
+            Err(e) if e.error_len().is_none() => {
+                self.carry = data.split_off(e.valid_up_to());
+            }
+            Err(e) => {
+                let rest = data.split_off(e.valid_up_to());
+                let encoding = detect(&rest);
+                let inner =
+                    std::mem::replace(&mut self.inner, Box::new(io::empty()));
+                let decoder = DecodeReaderBytesBuilder::new()
+                    .encoding(Some(encoding))
+                    .build(io::Cursor::new(rest).chain(inner));
+                self.decoder = Some(Box::new(decoder));
+            }
+        }
+        self.ready = data;
+        Ok(())
+    }
+}
+
+impl Read for Utf8Fallback {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.pos < self.ready.len() {
+                let len = (&self.ready[self.pos..]).read(buf)?;
+                self.pos += len;
+                return Ok(len);
+            }
+            if let Some(decoder) = &mut self.decoder {
+                return decoder.read(buf);
+            }
+            self.refill()?;
+            let done = self.ready.is_empty() && self.carry.is_empty();
+            if done && self.decoder.is_none() {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------
+/// A writer that re-encodes the UTF-8 written to it. Characters the
+/// target encoding cannot represent are written as `?`.
+pub struct EncodeWriter<W: Write> {
+    out: W,
+    encoding: &'static Encoding,
+    encoder: Encoder,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodeWriter<W> {
+    pub fn new(out: W, encoding: &'static Encoding) -> Self {
+        EncodeWriter {
+            out,
+            encoding,
+            encoder: encoding.new_encoder(),
+            pending: vec![],
+        }
+    }
+
+    fn encode(&mut self, text: &str) -> io::Result<()> {
+        // encoding_rs only decodes UTF-16, so encode it by hand.
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            let le = self.encoding == UTF_16LE;
+            let bytes: Vec<u8> = text
+                .encode_utf16()
+                .flat_map(|unit| {
+                    if le {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    }
+                })
+                .collect();
+            return self.out.write_all(&bytes);
+        }
+
+        let mut buf = [0; 4096];
+        let mut rest = text;
+        loop {
+            let (result, read, written) = self
+                .encoder
+                .encode_from_utf8_without_replacement(rest, &mut buf, false);
+            self.out.write_all(&buf[..written])?;
+            rest = &rest[read..];
+            match result {
+                EncoderResult::InputEmpty => return Ok(()),
+                EncoderResult::OutputFull => {}
+                EncoderResult::Unmappable(_) => self.out.write_all(b"?")?,
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for EncodeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let pending = std::mem::take(&mut self.pending);
+        let mut rest = &pending[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.encode(text)?;
+                    break;
+                }
+                Err(e) => {
+                    let (valid, invalid) = rest.split_at(e.valid_up_to());
+                    self.encode(
+                        std::str::from_utf8(valid).unwrap_or_default(),
+                    )?;
+                    match e.error_len() {
+                        // Keep a character split across writes for later.
+                        None => {
+                            self.pending = invalid.to_vec();
+                            break;
+                        }
+                        Some(len) => {
+                            self.out.write_all(b"?")?;
+                            rest = &invalid[len..];
+                        }
+                    }
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
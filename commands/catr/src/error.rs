@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Everything that can make catr fail.
+#[derive(Debug)]
+pub enum CatrError {
+    /// Invalid command-line arguments.
+    Usage(String),
+    /// An input could not be opened or read.
+    Input(String, io::Error),
+    /// Standard output could not be written.
+    Output(io::Error),
+    /// The inputs could not be watched for changes.
+    Watch(io::Error),
+    /// Some inputs failed; each one has been reported already.
+    Failed(usize),
+}
+
+impl CatrError {
+    pub(crate) fn input(filename: &str, e: io::Error) -> CatrError {
+        CatrError::Input(filename.to_string(), e)
+    }
+
+    /// The process exit status: 2 for usage errors, 1 for anything that
+    /// went wrong while reading or writing, like GNU cat.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CatrError::Usage(_) => 2,
+            _ => 1,
+        }
+    }
+
+    /// Whether the error needs no message of its own: failed inputs are
+    /// reported as they happen, and a closed pipe is not worth one.
+    pub fn is_silent(&self) -> bool {
+        match self {
+            CatrError::Failed(_) => true,
+            CatrError::Output(e) => e.kind() == io::ErrorKind::BrokenPipe,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for CatrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatrError::Usage(msg) => write!(f, "{}", msg),
+            CatrError::Input(filename, e) => write!(f, "{}: {}", filename, e),
+            CatrError::Output(e) => write!(f, "write error: {}", e),
+            CatrError::Watch(e) => write!(f, "cannot watch inputs: {}", e),
+            CatrError::Failed(count) => {
+                write!(f, "{} input(s) could not be read", count)
+            }
+        }
+    }
+}
+
+impl Error for CatrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CatrError::Input(_, e)
+            | CatrError::Output(e)
+            | CatrError::Watch(e) => Some(e),
+            CatrError::Usage(_) | CatrError::Failed(_) => None,
+        }
+    }
+}
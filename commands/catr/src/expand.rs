@@ -0,0 +1,78 @@
+use crate::CatrError;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+// --------------------------------------------------
+/// Expand glob patterns ourselves, so `catr 'logs/**/*.log'` behaves the
+/// same under every shell, and with `recursive` replace each directory by
+/// the visible files below it in name order. Anything that matches
+/// nothing is kept as is and reported when it fails to open. Directories
+/// that cannot be walked are reported here; their number is returned.
+pub fn expand(files: Vec<String>, recursive: bool) -> (Vec<String>, usize) {
+    let mut expanded = vec![];
+    let mut failed = 0;
+    for file in files {
+        for path in glob(&file) {
+            if recursive && Path::new(&path).is_dir() {
+                failed += walk(&path, &mut expanded);
+            } else {
+                expanded.push(path);
+            }
+        }
+    }
+    (expanded, failed)
+}
+
+// --------------------------------------------------
+fn glob(pattern: &str) -> Vec<String> {
+    if !pattern.contains(['*', '?', '[']) || Path::new(pattern).exists() {
+        return vec![pattern.to_string()];
+    }
+    let matches: Vec<String> = match glob::glob(pattern) {
+        Ok(paths) => paths
+            .filter_map(Result::ok)
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+        Err(_) => vec![],
+    };
+    if matches.is_empty() {
+        vec![pattern.to_string()]
+    } else {
+        matches
+    }
+}
+
+// --------------------------------------------------
+/// Push every non-directory below `dir`, reporting and counting the
+/// entries that could not be read. Hidden files and directories such as
+/// `.git` are skipped, but `dir` itself may be hidden, e.g. `.`.
+fn walk(dir: &str, files: &mut Vec<String>) -> usize {
+    let mut failed = 0;
+    let visible = |entry: &DirEntry| {
+        entry.depth() == 0 || !entry.file_name().as_bytes().starts_with(b".")
+    };
+    for entry in WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(visible)
+    {
+        match entry {
+            Ok(entry) if entry.file_type().is_dir() => {}
+            Ok(entry) => {
+                files.push(entry.path().to_string_lossy().into_owned())
+            }
+            Err(e) => {
+                let path = e.path().unwrap_or_else(|| Path::new(dir));
+                let path = path.to_string_lossy().into_owned();
+                let e = e.into_io_error().unwrap_or_else(|| {
+                    io::Error::other("file system loop detected")
+                });
+                eprintln!("{}", CatrError::input(&path, e));
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
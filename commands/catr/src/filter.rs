@@ -0,0 +1,139 @@
+use crate::{print_line, Config, Numbering};
+use colored::*;
+use regex::{Captures, Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+/// Which lines to keep, as set by `--grep`, `--exclude` and `--context`.
+#[derive(Debug)]
+pub struct Filter {
+    grep: Option<Regex>,
+    exclude: Option<Regex>,
+    context: usize,
+    color: bool,
+}
+
+impl Filter {
+    pub fn new(
+        grep: Option<&str>,
+        exclude: Option<&str>,
+        ignore_case: bool,
+        context: usize,
+        color: bool,
+    ) -> Result<Filter, regex::Error> {
+        let build = |pattern: &str| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+        };
+        Ok(Filter {
+            grep: grep.map(build).transpose()?,
+            exclude: exclude.map(build).transpose()?,
+            context,
+            color,
+        })
+    }
+
+    fn keeps(&self, line: &str) -> bool {
+        self.grep.as_ref().is_none_or(|re| re.is_match(line))
+            && !self.exclude.as_ref().is_some_and(|re| re.is_match(line))
+    }
+
+    // --------------------------------------------------
+    /// Color what `--grep` matched when writing to a terminal.
+    pub fn highlight<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        match &self.grep {
+            Some(re) if self.color => re
+                .replace_all(line, |caps: &Captures| {
+                    caps[0].red().bold().to_string()
+                }),
+            _ => Cow::Borrowed(line),
+        }
+    }
+}
+
+/// Per-file filtering state: the lines held back as leading context and
+/// how many trailing context lines are still due.
+pub struct Matcher<'a> {
+    filter: &'a Filter,
+    before: VecDeque<(Numbering, String)>,
+    after: usize,
+    printed: bool,
+    skipped: bool,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(filter: &'a Filter) -> Self {
+        Matcher {
+            filter,
+            before: VecDeque::with_capacity(filter.context),
+            after: 0,
+            printed: false,
+            skipped: false,
+        }
+    }
+
+    // --------------------------------------------------
+    /// Print `line` if it is kept or falls within the context of a kept
+    /// line. Like grep, `--` separates groups that are not adjacent.
+    pub fn feed(
+        &mut self,
+        out: &mut impl Write,
+        config: &Config,
+        numbering: Numbering,
+        line: String,
+    ) -> io::Result<()> {
+        let context = self.filter.context;
+        if self.filter.keeps(&line) {
+            if self.printed && self.skipped && context > 0 {
+                writeln!(out, "--")?;
+            }
+            for (numbering, line) in self.before.drain(..) {
+                print_line(out, config, numbering, &line)?;
+            }
+            print_line(out, config, numbering, &line)?;
+            self.after = context;
+            self.printed = true;
+            self.skipped = false;
+        } else if self.after > 0 {
+            print_line(out, config, numbering, &line)?;
+            self.after -= 1;
+        } else if context == 0 {
+            self.skipped = true;
+        } else {
+            if self.before.len() == context {
+                self.before.pop_front();
+                self.skipped = true;
+            }
+            self.before.push_back((numbering, line));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_matches() {
+        colored::control::set_override(true);
+        let filter = Filter::new(Some("o+"), None, false, 0, true).unwrap();
+        assert_eq!(
+            filter.highlight("foo bar"),
+            format!("f{} bar", "oo".red().bold())
+        );
+        let plain = Filter::new(Some("o+"), None, false, 0, false).unwrap();
+        assert_eq!(plain.highlight("foo bar"), "foo bar");
+    }
+
+    #[test]
+    fn keeps_lines() {
+        let filter =
+            Filter::new(Some("fox"), Some("lazy"), true, 0, false).unwrap();
+        assert!(filter.keeps("The quick brown FOX"));
+        assert!(!filter.keeps("The fox and the lazy dog"));
+        assert!(!filter.keeps("spiders"));
+    }
+}
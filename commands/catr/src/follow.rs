@@ -0,0 +1,243 @@
+use crate::{finish, open_file, print_header, CatrError, Config, MyResult};
+use inotify::{Inotify, WatchMask};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// A file followed by name, so a rotated log is picked up again.
+struct Followed {
+    name: String,
+    file: Option<File>,
+    inode: Option<(u64, u64)>,
+    pos: u64,
+    line_num: usize,
+    last_num: usize,
+    at_line_start: bool,
+}
+
+impl Followed {
+    fn new(name: &str) -> Followed {
+        let mut followed = Followed {
+            name: name.to_string(),
+            file: None,
+            inode: None,
+            pos: 0,
+            line_num: 0,
+            last_num: 0,
+            at_line_start: true,
+        };
+        match open_file(name) {
+            Err(e) => eprintln!("{}", CatrError::input(name, e)),
+            Ok(file) => followed.attach(file),
+        }
+        followed
+    }
+
+    fn attach(&mut self, file: File) {
+        self.inode = file.metadata().ok().map(|m| (m.dev(), m.ino()));
+        self.file = Some(file);
+        self.pos = 0;
+    }
+
+    // --------------------------------------------------
+    /// Copy whatever was appended since the last call to `out` a chunk at
+    /// a time, so a large log is neither held in memory nor held back
+    /// until it has been read to the end. With `header`, a header is
+    /// written before the first chunk. Read errors are reported and
+    /// following goes on. Returns whether anything was copied.
+    fn copy_new(
+        &mut self,
+        config: &Config,
+        out: &mut impl Write,
+        header: Option<bool>,
+    ) -> MyResult<bool> {
+        if let Err(e) = self.rewind_if_truncated() {
+            eprintln!("{}", CatrError::input(&self.name, e));
+        }
+        let mut buf = [0; 8192];
+        let mut copied = false;
+        loop {
+            let len = match self.file.as_mut().map(|file| file.read(&mut buf)) {
+                None | Some(Ok(0)) => return Ok(copied),
+                Some(Ok(len)) => len,
+                Some(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {
+                    continue
+                }
+                Some(Err(e)) => {
+                    eprintln!("{}", CatrError::input(&self.name, e));
+                    return Ok(copied);
+                }
+            };
+            if !copied {
+                if let Some(separate) = header {
+                    print_header(out, separate, &self.name)
+                        .map_err(CatrError::Output)?;
+                }
+                copied = true;
+            }
+            self.pos += len as u64;
+            self.write(config, &buf[..len], out)
+                .map_err(CatrError::Output)?;
+        }
+    }
+
+    // --------------------------------------------------
+    /// Start over if a regular file shrank underneath us.
+    fn rewind_if_truncated(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.file {
+            let meta = file.metadata()?;
+            if meta.is_file() && meta.len() < self.pos {
+                eprintln!("catr: {}: file truncated", self.name);
+                file.seek(SeekFrom::Start(0))?;
+                self.pos = 0;
+            }
+        }
+        Ok(())
+    }
+
+    // --------------------------------------------------
+    /// Switch to a new file if the name now points at a different inode,
+    /// as it does after logrotate moves the old one away.
+    fn reopen_if_replaced(&mut self) -> bool {
+        let inode = match fs::metadata(&self.name) {
+            Ok(meta) if meta.is_file() => Some((meta.dev(), meta.ino())),
+            _ => return false,
+        };
+        if inode == self.inode {
+            return false;
+        }
+        match File::open(&self.name) {
+            Err(_) => false,
+            Ok(file) => {
+                if self.file.is_some() {
+                    eprintln!(
+                        "catr: {}: file replaced; following new file",
+                        self.name
+                    );
+                }
+                self.attach(file);
+                true
+            }
+        }
+    }
+
+    // --------------------------------------------------
+    fn write(
+        &mut self,
+        config: &Config,
+        data: &[u8],
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            if self.at_line_start {
+                if config.number_lines {
+                    self.line_num += 1;
+                    write!(out, "{:6}\t", self.line_num)?;
+                } else if config.number_nonblank_lines && piece != b"\n" {
+                    self.last_num += 1;
+                    write!(out, "{:6}\t", self.last_num)?;
+                }
+            }
+            out.write_all(piece)?;
+            self.at_line_start = piece.ends_with(b"\n");
+        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+/// Print every file, then keep printing what gets appended to them.
+pub fn run(config: &Config) -> MyResult<()> {
+    let mut inotify = Inotify::init().map_err(CatrError::Watch)?;
+    let mut failed = config.unreadable;
+    let mut files = vec![];
+    for name in &config.files {
+        match followable(name) {
+            Err(e) => {
+                eprintln!("{}", CatrError::input(name, e));
+                failed += 1;
+            }
+            Ok(()) => {
+                watch(&mut inotify, name);
+                files.push(Followed::new(name));
+            }
+        }
+    }
+    if files.is_empty() {
+        return finish(failed);
+    }
+
+    let headers = config.headers || files.len() > 1;
+    let mut current = None;
+    let mut out = io::stdout();
+    for (i, followed) in files.iter_mut().enumerate() {
+        if headers {
+            print_header(&mut out, current.is_some(), &followed.name)
+                .map_err(CatrError::Output)?;
+        }
+        current = Some(i);
+        followed.copy_new(config, &mut out, None)?;
+    }
+    out.flush().map_err(CatrError::Output)?;
+
+    let mut buffer = [0; 4096];
+    loop {
+        // Which file an event was for hardly matters; checking them all
+        // also covers renames reported against the parent directory.
+        inotify
+            .read_events_blocking(&mut buffer)
+            .map_err(CatrError::Watch)?;
+        for (i, followed) in files.iter_mut().enumerate() {
+            // A header only when switching from another file's output.
+            let header =
+                |current| (headers && current != Some(i)).then_some(true);
+            if followed.copy_new(config, &mut out, header(current))? {
+                current = Some(i);
+            }
+            if followed.reopen_if_replaced() {
+                watch(&mut inotify, &followed.name);
+                if followed.copy_new(config, &mut out, header(current))? {
+                    current = Some(i);
+                }
+            }
+        }
+        out.flush().map_err(CatrError::Output)?;
+    }
+}
+
+// --------------------------------------------------
+/// Only regular files can be followed: standard input and FIFOs can be
+/// read only once and would block the whole loop, and devices never end.
+/// A file that does not exist yet is fine; it is picked up once created.
+fn followable(name: &str) -> io::Result<()> {
+    if name == "-" {
+        return Err(io::Error::other("cannot follow standard input"));
+    }
+    match fs::metadata(name) {
+        Ok(meta) if !meta.is_file() => {
+            Err(io::Error::other("cannot follow: not a regular file"))
+        }
+        _ => Ok(()),
+    }
+}
+
+// --------------------------------------------------
+/// Watch the file itself for writes and its directory for a replacement
+/// appearing under the same name.
+fn watch(inotify: &mut Inotify, name: &str) {
+    let dir = match Path::new(name).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let _ = inotify.watches().add(
+        name,
+        WatchMask::MODIFY
+            | WatchMask::ATTRIB
+            | WatchMask::MOVE_SELF
+            | WatchMask::DELETE_SELF,
+    );
+    let _ = inotify
+        .watches()
+        .add(dir, WatchMask::CREATE | WatchMask::MOVED_TO);
+}
@@ -0,0 +1,205 @@
+use crate::{
+    copy, copy_bytes, finish, keep_going, open, CatrError, Config, MyResult,
+    Range,
+};
+use std::io::{self, BufRead, Read, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexMode {
+    Dump {
+        cols: usize,
+        group: usize,
+        offset: u64,
+    },
+    Reverse {
+        offset: u64,
+    },
+}
+
+// --------------------------------------------------
+/// Dump all inputs as one stream, or rebuild the bytes from a dump.
+pub fn run(config: &Config, mode: HexMode) -> MyResult<()> {
+    let stdout = io::stdout();
+    let mut failed = config.unreadable;
+    match mode {
+        HexMode::Dump {
+            cols,
+            group,
+            offset,
+        } => {
+            // Like xxd -s, positions after --bytes are those in the file.
+            let skipped = match config.range {
+                Some(Range::Bytes(first, _)) => first as u64 - 1,
+                _ => 0,
+            };
+            let mut out =
+                HexWriter::new(stdout.lock(), cols, group, offset + skipped);
+            for filename in &config.files {
+                let result = open(filename, config.decompress)
+                    .map_err(|e| CatrError::input(filename, e))
+                    .and_then(|mut file| match config.range {
+                        Some(Range::Bytes(first, last)) => {
+                            copy_bytes(filename, file, first, last, &mut out)
+                        }
+                        _ => copy(filename, &mut file, &mut out),
+                    });
+                keep_going(result, &mut failed)?;
+            }
+            out.finish().map_err(CatrError::Output)?;
+        }
+        HexMode::Reverse { offset } => {
+            let mut out = stdout.lock();
+            let mut pos = 0;
+            for filename in &config.files {
+                let result = open(filename, config.decompress)
+                    .map_err(|e| CatrError::input(filename, e))
+                    .and_then(|file| {
+                        reverse(filename, file, offset, &mut pos, &mut out)
+                    });
+                keep_going(result, &mut failed)?;
+            }
+            out.flush().map_err(CatrError::Output)?;
+        }
+    }
+    finish(failed)
+}
+
+// --------------------------------------------------
+/// A writer that turns the bytes it receives into xxd-style lines.
+pub struct HexWriter<W: Write> {
+    out: W,
+    cols: usize,
+    group: usize,
+    offset: u64,
+    line: Vec<u8>,
+}
+
+impl<W: Write> HexWriter<W> {
+    pub fn new(out: W, cols: usize, group: usize, offset: u64) -> Self {
+        HexWriter {
+            out,
+            cols,
+            group: if group == 0 { cols } else { group },
+            offset,
+            line: Vec::with_capacity(cols),
+        }
+    }
+
+    /// Print the last, possibly short, line.
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            self.write_line()?;
+        }
+        self.out.flush()
+    }
+
+    fn write_line(&mut self) -> io::Result<()> {
+        let groups = self.cols.div_ceil(self.group);
+        let width = self.cols * 2 + groups - 1;
+        let mut hex = String::with_capacity(width);
+        for (i, byte) in self.line.iter().enumerate() {
+            if i > 0 && i % self.group == 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x}", byte));
+        }
+        let ascii: String = self
+            .line
+            .iter()
+            .map(|&b| {
+                if (0x20..0x7f).contains(&b) {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        writeln!(self.out, "{:08x}: {:width$}  {}", self.offset, hex, ascii)?;
+        self.offset += self.line.len() as u64;
+        self.line.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HexWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.line.push(byte);
+            if self.line.len() == self.cols {
+                self.write_line()?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// --------------------------------------------------
+/// Turn an xxd-style dump back into bytes. Gaps between the offsets are
+/// filled with zeros; `offset` is subtracted from every line's offset.
+fn reverse(
+    filename: &str,
+    input: impl BufRead,
+    offset: u64,
+    pos: &mut u64,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let invalid = |msg: String| {
+        CatrError::input(
+            filename,
+            io::Error::new(io::ErrorKind::InvalidData, msg),
+        )
+    };
+    for line_result in input.lines() {
+        let line = line_result.map_err(|e| CatrError::input(filename, e))?;
+        let (addr, rest) = match line.split_once(':') {
+            Some(parts) => parts,
+            None if line.trim().is_empty() => continue,
+            None => {
+                return Err(invalid(format!(
+                    "invalid hex dump line -- {}",
+                    line
+                )))
+            }
+        };
+        let addr = u64::from_str_radix(addr.trim(), 16)
+            .ok()
+            .and_then(|addr| addr.checked_sub(offset))
+            .ok_or_else(|| invalid(format!("invalid offset -- {}", addr)))?;
+        let hex = rest.trim_start_matches(' ');
+        let hex = hex.split("  ").next().unwrap_or_default();
+        let bytes = parse_hex(hex).ok_or_else(|| {
+            invalid(format!("invalid hex dump line -- {}", line))
+        })?;
+
+        if addr < *pos {
+            return Err(invalid(format!(
+                "cannot seek backwards to offset {:x}",
+                addr
+            )));
+        }
+        io::copy(&mut io::repeat(0).take(addr - *pos), out)
+            .and_then(|_| out.write_all(&bytes))
+            .map_err(CatrError::Output)?;
+        *pos = addr + bytes.len() as u64;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| *b != b' ').collect();
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()
+        })
+        .collect()
+}
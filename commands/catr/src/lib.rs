@@ -1,86 +1,599 @@
-use clap::{App, Arg};
-use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use bzip2::read::MultiBzDecoder;
+use clap::Parser;
+use cli::{Args, Generate};
+use encoding::EncodeWriter;
+use encoding_rs::{Encoding, UTF_8};
+use filter::{Filter, Matcher};
+use flate2::read::MultiGzDecoder;
+use hex::HexMode;
+use prefetch::Prefetched;
+use std::collections::VecDeque;
+use std::fs::{self, File, Metadata};
+use std::io::{
+    self, BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom, Write,
+};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use xz2::read::XzDecoder;
 
-type MyResult<T> = Result<T, Box<dyn Error>>;
+mod cli;
+mod encoding;
+mod error;
+mod expand;
+mod filter;
+mod follow;
+mod hex;
+mod prefetch;
+mod pretty;
+
+pub use error::CatrError;
+
+type MyResult<T> = Result<T, CatrError>;
 
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
     number_lines: bool,
     number_nonblank_lines: bool,
+    decompress: bool,
+    pretty: bool,
+    follow: bool,
+    range: Option<Range>,
+    hex: Option<HexMode>,
+    from_encoding: Option<&'static Encoding>,
+    to_encoding: &'static Encoding,
+    strip_bom: bool,
+    jobs: usize,
+    headers: bool,
+    filter: Option<Filter>,
+    /// Inputs that already failed while expanding the arguments.
+    unreadable: usize,
+    generate: Option<Generate>,
+}
+
+/// Which part of each input to print. Line and byte positions are
+/// 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Range {
+    Lines(usize, Option<usize>),
+    Tail(usize),
+    Bytes(usize, Option<usize>),
+}
+
+/// Line counters. Without a range they carry on from one file to the
+/// next like GNU cat (and tests/expected/all.n.out); catr used to
+/// restart them for every file.
+#[derive(Debug, Default, Clone, Copy)]
+struct Numbering {
+    line_num: usize,
+    last_num: usize,
+}
+
+impl Numbering {
+    fn advance(&mut self, line: &str) {
+        self.line_num += 1;
+        if !line.is_empty() {
+            self.last_num += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Identify a compressed stream by its leading magic bytes.
+    fn detect(magic: &[u8]) -> Option<Compression> {
+        if magic.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if magic.len() >= 10
+            && magic.starts_with(b"BZh")
+            && (b'1'..=b'9').contains(&magic[3])
+            && magic[4..10] == [0x31, 0x41, 0x59, 0x26, 0x53, 0x59]
+        {
+            Some(Compression::Bzip2)
+        } else {
+            None
+        }
+    }
 }
 
 // --------------------------------------------------
 pub fn get_args() -> MyResult<Config> {
-    let matches = App::new("catr")
-        .version("0.0.1")
-        .author("Norfloxaciner <1762161822@qq.com>")
-        .about("Rust cat")
-        .arg(
-            Arg::with_name("files")
-                .value_name("FILE")
-                .help("Input file(s)")
-                .multiple(true)
-                .default_value("-"),
-        )
-        .arg(
-            Arg::with_name("number")
-                .short("n")
-                .long("number")
-                .help("Number lines")
-                .takes_value(false)
-                .conflicts_with("number_nonblank"),
-        )
-        .arg(
-            Arg::with_name("number_nonblank")
-                .short("b")
-                .long("number-nonblank")
-                .help("Number non-blank lines")
-                .takes_value(false),
+    let args = Args::try_parse().map_err(|e| {
+        if e.use_stderr() {
+            CatrError::Usage(e.to_string().trim_end().to_string())
+        } else {
+            e.exit()
+        }
+    })?;
+
+    let range = if let Some((first, last)) = args.lines {
+        Some(Range::Lines(first, last))
+    } else if let Some(count) = args.head {
+        Some(Range::Lines(1, Some(count)))
+    } else if let Some(count) = args.tail {
+        Some(Range::Tail(count))
+    } else {
+        args.bytes.map(|(first, last)| Range::Bytes(first, last))
+    };
+
+    let offset = args.offset.unwrap_or(0);
+    let hex = if args.hex {
+        Some(HexMode::Dump {
+            cols: args.cols.unwrap_or(16),
+            group: args.group.unwrap_or(2),
+            offset,
+        })
+    } else if args.reverse {
+        Some(HexMode::Reverse { offset })
+    } else {
+        None
+    };
+
+    let from_encoding = args
+        .from_encoding
+        .as_deref()
+        .map(encoding::parse_encoding)
+        .transpose()
+        .map_err(|e| CatrError::Usage(format!("unknown encoding -- {}", e)))?;
+    let to_encoding = args
+        .to_encoding
+        .as_deref()
+        .map(encoding::parse_encoding)
+        .transpose()
+        .map_err(|e| CatrError::Usage(format!("unknown encoding -- {}", e)))?
+        .unwrap_or(UTF_8);
+    let filter = if args.grep.is_some() || args.exclude.is_some() {
+        let filter = Filter::new(
+            args.grep.as_deref(),
+            args.exclude.as_deref(),
+            args.ignore_case,
+            args.context.unwrap_or(0),
+            io::stdout().is_terminal(),
         )
-        .get_matches();
+        .map_err(|e| CatrError::Usage(format!("invalid pattern -- {}", e)))?;
+        Some(filter)
+    } else {
+        None
+    };
+    let (files, unreadable) = expand::expand(args.files, args.recursive);
 
     Ok(Config {
-        files: matches.values_of_lossy("files").unwrap(),
-        number_lines: matches.is_present("number"),
-        number_nonblank_lines: matches.is_present("number_nonblank"),
+        files,
+        number_lines: args.number,
+        number_nonblank_lines: args.number_nonblank,
+        decompress: args.decompress,
+        pretty: args.pretty,
+        follow: args.follow,
+        range,
+        hex,
+        from_encoding,
+        to_encoding,
+        strip_bom: args.strip_bom,
+        jobs: args.jobs.unwrap_or(1),
+        headers: args.headers,
+        filter,
+        unreadable,
+        generate: args.generate,
     })
 }
 
 // --------------------------------------------------
 pub fn run(config: Config) -> MyResult<()> {
-    for filename in config.files {
-        match open(&filename) {
-            Err(e) => eprintln!("{}: {}", filename, e),
-            Ok(file) => {
-                let mut last_num = 0;
-                for (line_num, line_result) in file.lines().enumerate() {
-                    let line = line_result?;
-                    if config.number_lines {
-                        println!("{:6}\t{}", line_num + 1, line);
-                    } else if config.number_nonblank_lines {
-                        if !line.is_empty() {
-                            last_num += 1;
-                            println!("{:6}\t{}", last_num, line);
-                        } else {
-                            println!();
-                        }
-                    } else {
-                        println!("{}", line);
-                    }
+    if let Some(kind) = config.generate {
+        return cli::generate(kind, &mut io::stdout().lock())
+            .map_err(CatrError::Output);
+    }
+    if config.follow {
+        return follow::run(&config);
+    }
+    if config.pretty && io::stdout().is_terminal() {
+        return pretty::run(&config);
+    }
+    if let Some(mode) = config.hex {
+        return hex::run(&config, mode);
+    }
+
+    let stdout = io::stdout();
+    let mut out: Box<dyn Write> = if config.to_encoding == UTF_8 {
+        Box::new(stdout.lock())
+    } else {
+        Box::new(EncodeWriter::new(stdout.lock(), config.to_encoding))
+    };
+
+    let mut numbering = Numbering::default();
+    let mut failed = config.unreadable;
+    let mut shown = 0;
+    let mut print = |filename: &str, input: io::Result<Prefetched>| {
+        // Ranges are taken per file, so numbers restart to stay the
+        // original line numbers.
+        if config.range.is_some() {
+            numbering = Numbering::default();
+        }
+        if config.headers {
+            print_header(&mut out, shown > 0, filename)
+                .map_err(CatrError::Output)?;
+            shown += 1;
+        }
+        let result = match input {
+            Ok(Prefetched::Stream) => {
+                cat_file(&mut out, &config, filename, &mut numbering)
+            }
+            Ok(Prefetched::Data(data)) => {
+                decode(Box::new(io::Cursor::new(data)), config.decompress)
+                    .map_err(|e| CatrError::input(filename, e))
+                    .and_then(|file| {
+                        cat_reader(
+                            &mut out,
+                            &config,
+                            filename,
+                            file,
+                            &mut numbering,
+                        )
+                    })
+            }
+            Err(e) => Err(CatrError::input(filename, e)),
+        };
+        keep_going(result, &mut failed)
+    };
+    if config.jobs > 1 {
+        prefetch::for_each(&config.files, config.jobs, &mut print)?;
+    } else {
+        for filename in &config.files {
+            print(filename, Ok(Prefetched::Stream))?;
+        }
+    }
+    out.flush().map_err(CatrError::Output)?;
+    finish(failed)
+}
+
+// --------------------------------------------------
+fn print_header(
+    out: &mut impl Write,
+    separate: bool,
+    name: &str,
+) -> io::Result<()> {
+    if separate {
+        writeln!(out)?;
+    }
+    writeln!(out, "==> {} <==", name)
+}
+
+// --------------------------------------------------
+/// Report a failed input and carry on with the next one like cat does;
+/// any other error stops everything.
+fn keep_going(result: MyResult<()>, failed: &mut usize) -> MyResult<()> {
+    match result {
+        Err(e @ CatrError::Input(..)) => {
+            eprintln!("{}", e);
+            *failed += 1;
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+// --------------------------------------------------
+fn finish(failed: usize) -> MyResult<()> {
+    match failed {
+        0 => Ok(()),
+        _ => Err(CatrError::Failed(failed)),
+    }
+}
+
+// --------------------------------------------------
+fn cat_file(
+    out: &mut impl Write,
+    config: &Config,
+    filename: &str,
+    numbering: &mut Numbering,
+) -> MyResult<()> {
+    let input = |e| CatrError::input(filename, e);
+    let numbered = config.number_lines || config.number_nonblank_lines;
+    let seeked = match config.range {
+        Some(Range::Tail(count))
+            if !numbered
+                && !config.decompress
+                && config.from_encoding.is_none() =>
+        {
+            seek_tail(filename, count)
+        }
+        _ => None,
+    };
+    let file = match seeked {
+        Some(file) => Box::new(file),
+        None => open(filename, config.decompress).map_err(input)?,
+    };
+    cat_reader(out, config, filename, file, numbering)
+}
+
+// --------------------------------------------------
+fn cat_reader(
+    out: &mut impl Write,
+    config: &Config,
+    filename: &str,
+    file: Box<dyn BufRead>,
+    numbering: &mut Numbering,
+) -> MyResult<()> {
+    let input = |e| CatrError::input(filename, e);
+    let file = match config.range {
+        Some(Range::Bytes(..)) => file,
+        _ => encoding::decode(file, config.from_encoding, config.strip_bom)
+            .map_err(input)?,
+    };
+
+    match config.range {
+        None => print_lines(out, config, filename, file, 1, None, numbering),
+        Some(Range::Lines(first, last)) => {
+            print_lines(out, config, filename, file, first, last, numbering)
+        }
+        Some(Range::Tail(count)) => {
+            print_tail(out, config, filename, file, count, numbering)
+        }
+        Some(Range::Bytes(first, last)) => {
+            copy_bytes(filename, file, first, last, out)
+        }
+    }
+}
+
+// --------------------------------------------------
+fn print_line(
+    out: &mut impl Write,
+    config: &Config,
+    numbering: Numbering,
+    line: &str,
+) -> io::Result<()> {
+    let line = match &config.filter {
+        Some(filter) => filter.highlight(line),
+        None => line.into(),
+    };
+    if config.number_lines {
+        writeln!(out, "{:6}\t{}", numbering.line_num, line)
+    } else if config.number_nonblank_lines && !line.is_empty() {
+        writeln!(out, "{:6}\t{}", numbering.last_num, line)
+    } else {
+        writeln!(out, "{}", line)
+    }
+}
+
+// --------------------------------------------------
+fn print_lines(
+    out: &mut impl Write,
+    config: &Config,
+    filename: &str,
+    file: Box<dyn BufRead>,
+    first: usize,
+    last: Option<usize>,
+    numbering: &mut Numbering,
+) -> MyResult<()> {
+    let mut matcher = config.filter.as_ref().map(Matcher::new);
+    for (index, line_result) in file.lines().enumerate() {
+        if last.is_some_and(|last| index >= last) {
+            break;
+        }
+        let line = line_result.map_err(|e| CatrError::input(filename, e))?;
+        numbering.advance(&line);
+        if index + 1 >= first {
+            match &mut matcher {
+                Some(matcher) => matcher.feed(out, config, *numbering, line),
+                None => print_line(out, config, *numbering, &line),
+            }
+            .map_err(CatrError::Output)?;
+        }
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn print_tail(
+    out: &mut impl Write,
+    config: &Config,
+    filename: &str,
+    file: Box<dyn BufRead>,
+    count: usize,
+    numbering: &mut Numbering,
+) -> MyResult<()> {
+    let mut tail = VecDeque::with_capacity(count);
+    for line_result in file.lines() {
+        let line = line_result.map_err(|e| CatrError::input(filename, e))?;
+        numbering.advance(&line);
+        if tail.len() == count {
+            tail.pop_front();
+        }
+        if count > 0 {
+            tail.push_back((*numbering, line));
+        }
+    }
+    let mut matcher = config.filter.as_ref().map(Matcher::new);
+    for (numbering, line) in tail {
+        match &mut matcher {
+            Some(matcher) => matcher.feed(out, config, numbering, line),
+            None => print_line(out, config, numbering, &line),
+        }
+        .map_err(CatrError::Output)?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn copy_bytes(
+    filename: &str,
+    mut file: Box<dyn BufRead>,
+    first: usize,
+    last: Option<usize>,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    io::copy(&mut file.by_ref().take(first as u64 - 1), &mut io::sink())
+        .map_err(|e| CatrError::input(filename, e))?;
+    let len = last.map_or(u64::MAX, |last| (last + 1 - first) as u64);
+    copy(filename, &mut file.take(len), out)
+}
+
+// --------------------------------------------------
+/// io::copy, but telling read errors apart from write errors.
+fn copy(
+    filename: &str,
+    file: &mut impl Read,
+    out: &mut impl Write,
+) -> MyResult<()> {
+    let mut buf = [0; 8192];
+    loop {
+        let len = match file.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(CatrError::input(filename, e)),
+        };
+        out.write_all(&buf[..len]).map_err(CatrError::Output)?;
+    }
+}
+
+// --------------------------------------------------
+/// Open a plain regular file positioned at its last `count` lines, or
+/// None when the input has to be streamed instead.
+fn seek_tail(filename: &str, count: usize) -> Option<BufReader<File>> {
+    if filename == "-" {
+        return None;
+    }
+    let mut file = open_file(filename).ok()?;
+    if !file.metadata().ok()?.is_file() {
+        return None;
+    }
+    let mut sample = [0; 8192];
+    let len = file.read(&mut sample).ok()?;
+    if Compression::detect(&sample[..len]).is_some()
+        || encoding::detect(&sample[..len]) != UTF_8
+    {
+        return None;
+    }
+    let offset = tail_offset(&mut file, count).ok()?;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    Some(BufReader::new(file))
+}
+
+// --------------------------------------------------
+/// Scan backwards from the end of `file` for the start of its last
+/// `count` lines, ignoring the final newline.
+fn tail_offset(file: &mut File, count: usize) -> io::Result<u64> {
+    let len = file.metadata()?.len();
+    if count == 0 {
+        return Ok(len);
+    }
+
+    let mut buf = [0; 8192];
+    let mut pos = len;
+    let mut newlines = 0;
+    while pos > 0 {
+        let size = buf.len().min(pos as usize);
+        pos -= size as u64;
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut buf[..size])?;
+        for i in (0..size).rev() {
+            let offset = pos + i as u64;
+            if buf[i] == b'\n' && offset + 1 != len {
+                newlines += 1;
+                if newlines == count {
+                    return Ok(offset + 1);
                 }
             }
         }
     }
+    Ok(0)
+}
+
+// --------------------------------------------------
+fn open(filename: &str, decompress: bool) -> io::Result<Box<dyn BufRead>> {
+    let reader: Box<dyn BufRead> = match filename {
+        "-" => {
+            let stdin = io::stdin();
+            check_input(&fd_metadata(stdin.as_fd())?)?;
+            Box::new(BufReader::new(stdin))
+        }
+        _ => Box::new(BufReader::new(open_file(filename)?)),
+    };
+    decode(reader, decompress)
+}
+
+// --------------------------------------------------
+/// Open a named input after checking what kind of file it is. FIFOs and
+/// character devices are fine and simply streamed.
+fn open_file(filename: &str) -> io::Result<File> {
+    check_input(&fs::metadata(filename)?)?;
+    File::open(filename)
+}
+
+// --------------------------------------------------
+/// Refuse inputs that cannot be read as a stream of bytes, and a regular
+/// file that is also standard output: cat would keep reading back what
+/// it just wrote and never finish.
+fn check_input(meta: &Metadata) -> io::Result<()> {
+    let kind = meta.file_type();
+    if kind.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::IsADirectory,
+            "Is a directory",
+        ));
+    }
+    if kind.is_socket() {
+        return Err(io::Error::other("cannot read from a socket"));
+    }
+    if kind.is_file() && meta.len() > 0 && is_output(meta) {
+        return Err(io::Error::other("input file is output file"));
+    }
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> MyResult<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn is_output(meta: &Metadata) -> bool {
+    fd_metadata(io::stdout().as_fd()).is_ok_and(|out| {
+        out.is_file() && out.dev() == meta.dev() && out.ino() == meta.ino()
+    })
+}
+
+// --------------------------------------------------
+fn fd_metadata(fd: BorrowedFd) -> io::Result<Metadata> {
+    File::from(fd.try_clone_to_owned()?).metadata()
+}
+
+// --------------------------------------------------
+/// Wrap `reader` in a streaming decoder when it starts with a known
+/// compression header. With `required`, anything else is an error.
+fn decode(
+    mut reader: Box<dyn BufRead>,
+    required: bool,
+) -> io::Result<Box<dyn BufRead>> {
+    let magic = reader.fill_buf()?;
+    let compression = Compression::detect(magic);
+    if compression.is_none() && required && !magic.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not in a recognized compressed format",
+        ));
     }
+
+    Ok(match compression {
+        Some(Compression::Gzip) => {
+            Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+        }
+        Some(Compression::Xz) => {
+            Box::new(BufReader::new(XzDecoder::new_multi_decoder(reader)))
+        }
+        Some(Compression::Zstd) => {
+            Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?))
+        }
+        Some(Compression::Bzip2) => {
+            Box::new(BufReader::new(MultiBzDecoder::new(reader)))
+        }
+        None => reader,
+    })
 }
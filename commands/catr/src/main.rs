@@ -1,6 +1,8 @@
 fn main() {
     if let Err(e) = catr::get_args().and_then(catr::run) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        if !e.is_silent() {
+            eprintln!("{}", e);
+        }
+        std::process::exit(e.exit_code());
     }
 }
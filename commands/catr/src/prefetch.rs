@@ -0,0 +1,119 @@
+use crate::{check_input, fd_metadata, open_file, CatrError, MyResult};
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::os::fd::AsFd;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+/// Files bigger than this are left for the printing thread to stream.
+const MAX_PREFETCH: u64 = 1 << 20;
+
+/// What a worker made of one input.
+pub enum Prefetched {
+    /// The whole file, read ahead of time.
+    Data(Vec<u8>),
+    /// Standard input, a FIFO or a large file: read it when its turn comes.
+    Stream,
+}
+
+// --------------------------------------------------
+/// Read `files` on `jobs` worker threads and hand them to `print` in
+/// argument order. Workers stay at most `2 * jobs` files ahead of the
+/// printer, which bounds memory; an error from `print` stops them.
+pub fn for_each(
+    files: &[String],
+    jobs: usize,
+    mut print: impl FnMut(&str, io::Result<Prefetched>) -> MyResult<()>,
+) -> MyResult<()> {
+    let window = jobs * 2;
+    let next = AtomicUsize::new(0);
+    // How many files have been printed, or usize::MAX once we give up.
+    let printed = Mutex::new(0usize);
+    let turn = Condvar::new();
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        let mut workers = vec![];
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let (next, printed, turn) = (&next, &printed, &turn);
+            workers.push(scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                if i >= files.len() {
+                    return;
+                }
+                let guard = printed.lock().unwrap();
+                let guard = turn
+                    .wait_while(guard, |done| i >= done.saturating_add(window))
+                    .unwrap();
+                if *guard == usize::MAX {
+                    return;
+                }
+                drop(guard);
+                // The printer waits for every file in turn, so a panic
+                // has to become an error for this one.
+                let data = panic::catch_unwind(|| read(&files[i]))
+                    .unwrap_or_else(|_| Err(worker_died()));
+                if tx.send((i, data)).is_err() {
+                    return;
+                }
+            }));
+        }
+        drop(tx);
+
+        let mut pending = BTreeMap::new();
+        let mut result = Ok(());
+        'files: for (i, filename) in files.iter().enumerate() {
+            let data = loop {
+                if let Some(data) = pending.remove(&i) {
+                    break data;
+                }
+                match rx.recv() {
+                    Ok((j, data)) => pending.insert(j, data),
+                    // Every worker is gone without sending this file.
+                    Err(_) => {
+                        result = Err(CatrError::input(filename, worker_died()));
+                        break 'files;
+                    }
+                };
+            };
+            result = print(filename, data);
+            let mut done = printed.lock().unwrap();
+            *done = if result.is_ok() { i + 1 } else { usize::MAX };
+            turn.notify_all();
+            if result.is_err() {
+                break;
+            }
+        }
+        // Join the workers here, or the scope would repeat a worker's
+        // panic on this thread after it has been reported as an error.
+        for worker in workers {
+            let _ = worker.join();
+        }
+        result
+    })
+}
+
+// --------------------------------------------------
+fn worker_died() -> io::Error {
+    io::Error::other("read-ahead worker died")
+}
+
+// --------------------------------------------------
+fn read(filename: &str) -> io::Result<Prefetched> {
+    if filename == "-" {
+        check_input(&fd_metadata(io::stdin().as_fd())?)?;
+        return Ok(Prefetched::Stream);
+    }
+    let mut file = open_file(filename)?;
+    let meta = file.metadata()?;
+    if !meta.is_file() || meta.len() > MAX_PREFETCH {
+        return Ok(Prefetched::Stream);
+    }
+    let mut data = Vec::with_capacity(meta.len() as usize);
+    file.read_to_end(&mut data)?;
+    Ok(Prefetched::Data(data))
+}
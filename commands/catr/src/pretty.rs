@@ -0,0 +1,373 @@
+use crate::{encoding, finish, keep_going, open, CatrError, Config, MyResult};
+use colored::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::as_24_bit_terminal_escaped;
+
+const THEME: &str = "base16-ocean.dark";
+/// The gutter is never narrower than four digits.
+const DIGITS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Added,
+    Modified,
+    RemovedAbove,
+}
+
+/// What stays the same for every file.
+struct Page<'a> {
+    syntaxes: &'a SyntaxSet,
+    theme: &'a Theme,
+    width: usize,
+    /// -b: number only the lines that are not blank.
+    nonblank: bool,
+}
+
+// --------------------------------------------------
+/// Highlight every input and send the result through the pager.
+pub fn run(config: &Config) -> MyResult<()> {
+    let syntaxes = SyntaxSet::load_defaults_nonewlines();
+    let themes = ThemeSet::load_defaults();
+    let page = Page {
+        syntaxes: &syntaxes,
+        theme: &themes.themes[THEME],
+        width: crossterm::terminal::size()
+            .map(|(cols, _)| cols as usize)
+            .unwrap_or(80),
+        nonblank: config.number_nonblank_lines,
+    };
+
+    let mut pager = spawn_pager();
+    let result = match pager.as_mut().and_then(|child| child.stdin.take()) {
+        Some(stdin) => {
+            print_files(config, &page, &mut io::BufWriter::new(stdin))
+        }
+        None => print_files(config, &page, &mut io::stdout().lock()),
+    };
+    if let Some(mut child) = pager {
+        child.wait().map_err(CatrError::Output)?;
+    }
+
+    match result {
+        Err(CatrError::Output(e)) if e.kind() == io::ErrorKind::BrokenPipe => {
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+// --------------------------------------------------
+fn print_files(
+    config: &Config,
+    page: &Page,
+    out: &mut dyn Write,
+) -> MyResult<()> {
+    let mut failed = config.unreadable;
+    for filename in &config.files {
+        let result = open(filename, config.decompress)
+            .and_then(|file| {
+                encoding::decode(file, config.from_encoding, config.strip_bom)
+            })
+            .map_err(|e| CatrError::input(filename, e))
+            .and_then(|file| {
+                let count = count_lines(filename, config.decompress);
+                let markers = git_markers(filename);
+                print_file(out, page, filename, file, count, &markers)
+            });
+        keep_going(result, &mut failed)?;
+    }
+    out.flush().map_err(CatrError::Output)?;
+    finish(failed)
+}
+
+// --------------------------------------------------
+/// Count the lines of a regular file up front so the gutter fits the
+/// largest line number. Pipes and devices can be read only once, so
+/// they get None and the gutter widens as the numbers grow instead.
+fn count_lines(filename: &str, decompress: bool) -> Option<usize> {
+    if filename == "-" || !fs::metadata(filename).ok()?.is_file() {
+        return None;
+    }
+    let mut file = open(filename, decompress).ok()?;
+    let (mut count, mut partial) = (0, false);
+    loop {
+        let buf = file.fill_buf().ok()?;
+        if buf.is_empty() {
+            return Some(count + partial as usize);
+        }
+        count += buf.iter().filter(|&&byte| byte == b'\n').count();
+        partial = buf.last() != Some(&b'\n');
+        let len = buf.len();
+        file.consume(len);
+    }
+}
+
+// --------------------------------------------------
+/// Highlight one input line by line. `count` is the number of lines
+/// when known, to size the gutter.
+fn print_file(
+    out: &mut dyn Write,
+    page: &Page,
+    filename: &str,
+    file: Box<dyn BufRead>,
+    count: Option<usize>,
+    markers: &HashMap<usize, Marker>,
+) -> MyResult<()> {
+    let output = CatrError::Output;
+    let mut lines = file
+        .lines()
+        .map(|line| line.map_err(|e| CatrError::input(filename, e)));
+    let first = lines.next().transpose()?;
+    let syntax = detect_syntax(page.syntaxes, filename, first.as_ref());
+
+    let mut digits = count.map_or(0, |count| count.to_string().len());
+    digits = digits.max(DIGITS);
+    let gutter = digits + 3;
+    let rule = |joint: &str| {
+        format!(
+            "{}{}{}",
+            "─".repeat(gutter),
+            joint,
+            "─".repeat(page.width.saturating_sub(gutter + 1))
+        )
+        .bright_black()
+    };
+    let name = if filename == "-" { "STDIN" } else { filename };
+
+    writeln!(out, "{}", rule("┬")).map_err(output)?;
+    writeln!(
+        out,
+        "{:gutter$}{} File: {}",
+        "",
+        "│".bright_black(),
+        name.bold()
+    )
+    .map_err(output)?;
+    writeln!(out, "{}", rule("┼")).map_err(output)?;
+
+    let mut highlighter = HighlightLines::new(syntax, page.theme);
+    let mut nonblank = 0;
+    for (index, line) in first.into_iter().map(Ok).chain(lines).enumerate() {
+        let line = line?;
+        let line_num = index + 1;
+        let marker = match markers.get(&line_num) {
+            Some(Marker::Added) => "+".green(),
+            Some(Marker::Modified) => "~".yellow(),
+            Some(Marker::RemovedAbove) => "‾".red(),
+            None => " ".normal(),
+        };
+        let number = if !page.nonblank {
+            line_num.to_string()
+        } else if line.is_empty() {
+            String::new()
+        } else {
+            nonblank += 1;
+            nonblank.to_string()
+        };
+        digits = digits.max(number.len());
+        let ranges = highlighter
+            .highlight_line(&line, page.syntaxes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            .map_err(|e| CatrError::input(filename, e))?;
+        writeln!(
+            out,
+            "{}{} {} {}\x1b[0m",
+            marker,
+            format!("{:>digits$} ", number).bright_black(),
+            "│".bright_black(),
+            as_24_bit_terminal_escaped(&ranges, false)
+        )
+        .map_err(output)?;
+    }
+
+    writeln!(out, "{}", rule("┴")).map_err(output)
+}
+
+// --------------------------------------------------
+/// Pick a syntax by file name or extension, then by the first line
+/// (shebangs, modelines), falling back to plain text.
+fn detect_syntax<'a>(
+    syntaxes: &'a SyntaxSet,
+    filename: &str,
+    first_line: Option<&String>,
+) -> &'a SyntaxReference {
+    let path = Path::new(filename);
+    path.file_name()
+        .and_then(|name| {
+            syntaxes.find_syntax_by_extension(&name.to_string_lossy())
+        })
+        .or_else(|| {
+            path.extension().and_then(|ext| {
+                syntaxes.find_syntax_by_extension(&ext.to_string_lossy())
+            })
+        })
+        .or_else(|| {
+            first_line.and_then(|line| syntaxes.find_syntax_by_first_line(line))
+        })
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+// --------------------------------------------------
+/// Ask git for the unstaged changes to `filename` and mark the lines
+/// they touch. Files outside a repository simply get no markers.
+fn git_markers(filename: &str) -> HashMap<usize, Marker> {
+    let path = Path::new(filename);
+    let (dir, name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) if filename != "-" => (dir, name),
+        _ => return HashMap::new(),
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--no-color", "--no-ext-diff", "-U0", "--"])
+        .arg(name)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_diff(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+// --------------------------------------------------
+fn parse_diff(diff: &str) -> HashMap<usize, Marker> {
+    let mut markers = HashMap::new();
+    for line in diff.lines().filter(|line| line.starts_with("@@ ")) {
+        let mut ranges = line.split_whitespace().skip(1);
+        let (old, new) = match (ranges.next(), ranges.next()) {
+            (Some(old), Some(new)) => (parse_range(old), parse_range(new)),
+            _ => continue,
+        };
+        let ((_, removed), (start, added)) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            _ => continue,
+        };
+
+        if added == 0 {
+            markers.insert(start + 1, Marker::RemovedAbove);
+        } else {
+            let marker = if removed == 0 {
+                Marker::Added
+            } else {
+                Marker::Modified
+            };
+            for line_num in start..start + added {
+                markers.insert(line_num, marker);
+            }
+        }
+    }
+    markers
+}
+
+// --------------------------------------------------
+/// Parse a hunk range such as `-12,3` or `+7` into (start, count).
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let range = range.trim_start_matches(['-', '+']);
+    match range.split_once(',') {
+        Some((start, count)) => {
+            Some((start.parse().ok()?, count.parse().ok()?))
+        }
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+// --------------------------------------------------
+/// Start `$PAGER` (default `less -RF`) with a pipe to its stdin.
+fn spawn_pager() -> Option<Child> {
+    let pager = std::env::var("PAGER").unwrap_or_default();
+    let mut words = pager.split_whitespace();
+    let (program, mut args) = match words.next() {
+        Some(program) => (program, words.collect::<Vec<_>>()),
+        None => ("less", vec![]),
+    };
+    if program == "less" && args.is_empty() {
+        args = vec!["-RF"];
+    }
+
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(input: &str, count: Option<usize>, nonblank: bool) -> String {
+        colored::control::set_override(false);
+        let syntaxes = SyntaxSet::load_defaults_nonewlines();
+        let themes = ThemeSet::load_defaults();
+        let page = Page {
+            syntaxes: &syntaxes,
+            theme: &themes.themes[THEME],
+            width: 20,
+            nonblank,
+        };
+        let file = Box::new(io::Cursor::new(input.to_string()));
+        let mut out = vec![];
+        print_file(&mut out, &page, "a.txt", file, count, &HashMap::new())
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn numbers_lines_in_the_gutter() {
+        let text = render("a\n\nb\n", Some(3), false);
+        let lines: Vec<_> = text.lines().collect();
+        assert!(lines[3].starts_with("    1  │ "));
+        assert!(lines[5].starts_with("    3  │ "));
+
+        let text = render("a\n\nb\n", Some(3), true);
+        let lines: Vec<_> = text.lines().collect();
+        assert!(lines[3].starts_with("    1  │ "));
+        assert!(lines[4].starts_with("       │ "));
+        assert!(lines[5].starts_with("    2  │ "));
+
+        let text = render("a\n", Some(123456), false);
+        assert!(text.lines().nth(3).unwrap().starts_with("      1  │ "));
+    }
+
+    #[test]
+    fn parses_hunk_ranges() {
+        assert_eq!(parse_range("-12,3"), Some((12, 3)));
+        assert_eq!(parse_range("+7"), Some((7, 1)));
+        assert_eq!(parse_range("+0,0"), Some((0, 0)));
+        assert_eq!(parse_range("+x"), None);
+    }
+
+    #[test]
+    fn marks_diff_hunks() {
+        let diff = "\
+@@ -3,0 +4,2 @@
++added
++added
+@@ -10 +12 @@
+-old
++new
+@@ -20,2 +21,0 @@
+-gone
+-gone
+";
+        let markers = parse_diff(diff);
+        assert_eq!(markers.get(&4), Some(&Marker::Added));
+        assert_eq!(markers.get(&5), Some(&Marker::Added));
+        assert_eq!(markers.get(&12), Some(&Marker::Modified));
+        assert_eq!(markers.get(&22), Some(&Marker::RemovedAbove));
+        assert_eq!(markers.len(), 4);
+    }
+}
@@ -1,8 +1,13 @@
-use assert_cmd::Command;
+use assert_cmd::{cargo::CommandCargoExt, Command};
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
 use std::error::Error;
 use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
 
 type TestResult = Result<(), Box<dyn Error>>;
 
@@ -11,6 +16,16 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const FOX_GZ: &str = "tests/inputs/fox.txt.gz";
+const SPIDERS_XZ: &str = "tests/inputs/spiders.txt.xz";
+const BUSTLE_ZST: &str = "tests/inputs/the-bustle.txt.zst";
+const BUSTLE_BZ2: &str = "tests/inputs/the-bustle.txt.bz2";
+const BUSTLE_GZ: &str = "tests/inputs/the-bustle.txt.gz";
+const BUSTLE_UTF16: &str = "tests/inputs/the-bustle.utf16.txt";
+const SPRING: &str = "tests/inputs/spring.txt";
+const SPRING_GBK: &str = "tests/inputs/spring.gbk.txt";
+const CAFE_LATIN1: &str = "tests/inputs/cafe.latin1.txt";
+const TREE: &str = "tests/inputs/tree";
 
 // --------------------------------------------------
 #[test]
@@ -24,6 +39,37 @@ fn usage() -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn generates_completions() -> TestResult {
+    for (shell, expected) in &[
+        ("bash", "complete -F _catr"),
+        ("zsh", "#compdef catr"),
+        ("fish", "complete -c catr -l lines"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(["--generate", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(*expected));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn generates_man_page() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--generate", "man"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".TH catr 1"))
+        .stdout(predicate::str::contains(
+            "\\fB\\-n\\fR, \\fB\\-\\-number\\fR\nNumber lines\n",
+        ));
+    Ok(())
+}
+
 // --------------------------------------------------
 fn gen_bad_file() -> String {
     loop {
@@ -47,14 +93,97 @@ fn skips_bad_file() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .failure()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn continues_past_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args([&bad, FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::starts_with(format!("{}: ", bad)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn continues_past_bad_file_in_hex() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox.txt.hex.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--hex", &bad, FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_directory() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(expected)
+        .stderr("tests/inputs: Is a directory\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_input_is_output() -> TestResult {
+    let path = temp_path("out.txt");
+    fs::copy(FOX, &path)?;
+    let out = fs::OpenOptions::new().append(true).open(&path)?;
+    let output = std::process::Command::cargo_bin(PRG)?
+        .arg(&path)
+        .stdout(out)
+        .output()?;
+    let contents = fs::read_to_string(&path)?;
+    fs::remove_file(&path)?;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?
+        .ends_with(": input file is output file\n"));
+    assert_eq!(contents, fs::read_to_string(FOX)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reads_fifo() -> TestResult {
+    let path = temp_path("fifo");
+    let status = std::process::Command::new("mkfifo").arg(&path).status()?;
+    assert!(status.success());
+    let writer = {
+        let path = path.clone();
+        thread::spawn(move || fs::write(path, "through a pipe\n"))
+    };
+    let output = Command::cargo_bin(PRG)?.arg(&path).output()?;
+    writer.join().unwrap()?;
+    fs::remove_file(&path)?;
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "through a pipe\n");
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> TestResult {
-    let expected = fs::read_to_string(expected_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .assert()
@@ -69,8 +198,8 @@ fn run_stdin(
     args: &[&str],
     expected_file: &str,
 ) -> TestResult {
-    let input = fs::read_to_string(input_file)?;
-    let expected = fs::read_to_string(expected_file)?;
+    let input = fs::read(input_file)?;
+    let expected = fs::read(expected_file)?;
     Command::cargo_bin(PRG)?
         .args(args)
         .write_stdin(input)
@@ -193,8 +322,558 @@ fn all_n() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-n"], "tests/expected/all.n.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn all_n_jobs() -> TestResult {
+    run(
+        &["-j", "3", FOX, SPIDERS, BUSTLE, "-n"],
+        "tests/expected/all.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn jobs_keep_order_past_bad_file() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["--jobs=2", FOX, &bad, SPIDERS, "tests/inputs", BUSTLE])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(expected)
+        .stderr(format!(
+            "{}: No such file or directory (os error 2)\n\
+             tests/inputs: Is a directory\n",
+            bad
+        ));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn all_b() -> TestResult {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn fox_gz() -> TestResult {
+    run(&[FOX_GZ], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn spiders_xz_n() -> TestResult {
+    run(&["-n", SPIDERS_XZ], "tests/expected/spiders.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_zst_b() -> TestResult {
+    run(&["-b", BUSTLE_ZST], "tests/expected/the-bustle.txt.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_bz2() -> TestResult {
+    run(&["-z", BUSTLE_BZ2], "tests/expected/the-bustle.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz_stdin_n() -> TestResult {
+    run_stdin(
+        BUSTLE_GZ,
+        &["-n", "-"],
+        "tests/expected/the-bustle.txt.n.stdin.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_rejects_plain_file() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-z", FOX])
+        .assert()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "not in a recognized compressed format",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn pretty_piped_is_plain() -> TestResult {
+    run(
+        &["--pretty", "-n", SPIDERS],
+        "tests/expected/spiders.txt.n.out",
+    )
+}
+
+// --------------------------------------------------
+fn temp_path(suffix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("catr-{}-{}", gen_bad_file(), suffix))
+}
+
+// --------------------------------------------------
+/// Run `catr -f` on `files`, apply `update` once it is watching, and
+/// return everything it printed before being killed.
+fn follow(
+    args: &[&str],
+    files: &[&PathBuf],
+    update: impl FnOnce() -> std::io::Result<()>,
+) -> Result<(String, String), Box<dyn Error>> {
+    let mut child =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .arg("--follow")
+            .args(args)
+            .args(files)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+    thread::sleep(Duration::from_millis(300));
+    update()?;
+    thread::sleep(Duration::from_millis(300));
+    child.kill()?;
+    let output = child.wait_with_output()?;
+    for file in files {
+        let _ = fs::remove_file(file);
+    }
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+    ))
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_appended() -> TestResult {
+    let log = temp_path("app.log");
+    fs::write(&log, "one\n")?;
+    let (stdout, _) = follow(&["-n"], &[&log], || {
+        let mut file = fs::OpenOptions::new().append(true).open(&log)?;
+        file.write_all(b"two\nthree\n")
+    })?;
+    assert_eq!(stdout, "     1\tone\n     2\ttwo\n     3\tthree\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_truncated() -> TestResult {
+    let log = temp_path("app.log");
+    fs::write(&log, "old contents\n")?;
+    let (stdout, stderr) = follow(&[], &[&log], || fs::write(&log, "new\n"))?;
+    assert_eq!(stdout, "old contents\nnew\n");
+    assert!(stderr.contains("file truncated"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_rotated() -> TestResult {
+    let log = temp_path("app.log");
+    let rotated = temp_path("app.log.1");
+    fs::write(&log, "before\n")?;
+    let (stdout, stderr) = follow(&[], &[&log], || {
+        fs::rename(&log, &rotated)?;
+        fs::write(&log, "after\n")
+    })?;
+    let _ = fs::remove_file(&rotated);
+    assert_eq!(stdout, "before\nafter\n");
+    assert!(stderr.contains("following new file"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_rejects_streams() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--follow", "-", "tests/inputs"])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("-: cannot follow standard input"))
+        .stderr(predicate::str::contains("tests/inputs: cannot follow"));
+
+    for arg in &["-z", "--grep=fox", "--head=1", "--bytes=1:2"] {
+        Command::cargo_bin(PRG)?
+            .args(["--follow", arg, FOX])
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_headers() -> TestResult {
+    let first = temp_path("first.log");
+    let second = temp_path("second.log");
+    fs::write(&first, "a\n")?;
+    fs::write(&second, "b\n")?;
+    let (stdout, _) = follow(&[], &[&first, &second], || {
+        let mut file = fs::OpenOptions::new().append(true).open(&first)?;
+        file.write_all(b"c\n")
+    })?;
+    let expected = format!(
+        "==> {0} <==\na\n\n==> {1} <==\nb\n\n==> {0} <==\nc\n",
+        first.display(),
+        second.display()
+    );
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_lines_n() -> TestResult {
+    run(
+        &["-n", "--lines=5:7", BUSTLE],
+        "tests/expected/the-bustle.txt.lines.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_head() -> TestResult {
+    run(
+        &["--head", "2", BUSTLE],
+        "tests/expected/the-bustle.txt.head.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_tail() -> TestResult {
+    run(
+        &["--tail", "3", BUSTLE],
+        "tests/expected/the-bustle.txt.tail.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz_tail_n() -> TestResult {
+    run(
+        &["-n", "--tail", "3", BUSTLE_GZ],
+        "tests/expected/the-bustle.txt.tail.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_stdin_tail() -> TestResult {
+    run_stdin(
+        BUSTLE,
+        &["--tail", "3"],
+        "tests/expected/the-bustle.txt.tail.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_bytes() -> TestResult {
+    run(&["--bytes=5:9", FOX], "tests/expected/fox.txt.bytes.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_range() -> TestResult {
+    for (arg, expected) in &[
+        ("--jobs=0", "illegal job count -- 0"),
+        ("--lines=3:1", "illegal line range -- 3:1"),
+        ("--lines=0:2", "illegal line range -- 0:2"),
+        ("--bytes=x:", "illegal byte range -- x:"),
+        ("--tail=-1", "illegal line count -- -1"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args([arg, FOX])
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicate::str::contains(*expected));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_pretty_range() -> TestResult {
+    for arg in &["--lines=1:2", "--head=1", "--tail=1", "--bytes=1:2"] {
+        Command::cargo_bin(PRG)?
+            .args(["--pretty", arg, FOX])
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_hex() -> TestResult {
+    run(&["--hex", FOX], "tests/expected/fox.txt.hex.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_offset_without_hex() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--offset", "0x10", FOX])
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("--offset"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_hex_bytes() -> TestResult {
+    run(
+        &["--hex", "--bytes=5:9", FOX],
+        "tests/expected/fox.txt.hex.s4.l5.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_gz_hex() -> TestResult {
+    run(&["-x", BUSTLE_GZ], "tests/expected/the-bustle.txt.hex.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn spiders_hex_layout() -> TestResult {
+    run(
+        &[
+            "-x", "--group", "1", "--cols", "8", "--offset", "256", SPIDERS,
+        ],
+        "tests/expected/spiders.txt.hex.g1.c8.o256.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_hex_ungrouped() -> TestResult {
+    run(
+        &["-x", "--group=0", "--cols=10", BUSTLE],
+        "tests/expected/the-bustle.txt.hex.g0.c10.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_xxd_dump() -> TestResult {
+    run(&["--reverse", "tests/expected/fox.txt.hex.out"], FOX)
+}
+
+// --------------------------------------------------
+#[test]
+fn hex_round_trip() -> TestResult {
+    let input = temp_path("random.bin");
+    let bytes: Vec<u8> = (0..5000).map(|_| rand::random()).collect();
+    fs::write(&input, &bytes)?;
+
+    for layout in &[
+        vec![],
+        vec!["--cols=7", "--group=3"],
+        vec!["--cols=256", "--group=0", "--offset=0x1000"],
+    ] {
+        let dump = Command::cargo_bin(PRG)?
+            .arg("--hex")
+            .args(layout)
+            .arg(&input)
+            .output()?;
+        let offset = layout
+            .iter()
+            .find(|arg| arg.starts_with("--offset"))
+            .map_or("--offset=0", |arg| *arg);
+        Command::cargo_bin(PRG)?
+            .args(["--reverse", offset])
+            .write_stdin(dump.stdout)
+            .assert()
+            .success()
+            .stdout(bytes.clone());
+    }
+    fs::remove_file(&input)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn spring_gbk_n() -> TestResult {
+    run(&["-n", SPRING_GBK], "tests/expected/spring.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn gbk_after_ascii_prefix() -> TestResult {
+    let prefix = "ascii only\n".repeat(2000);
+    let input = temp_path("late.gbk.txt");
+    let mut data = prefix.clone().into_bytes();
+    data.extend(fs::read(SPRING_GBK)?);
+    fs::write(&input, data)?;
+    let expected = prefix + &fs::read_to_string(SPRING)?;
+    let result = Command::cargo_bin(PRG)?.arg(&input).assert();
+    fs::remove_file(&input)?;
+    result.success().stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn spring_gbk_explicit_n() -> TestResult {
+    run(
+        &["-n", "--from-encoding", "gbk", SPRING_GBK],
+        "tests/expected/spring.txt.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn spring_to_gbk() -> TestResult {
+    run(&["--to-encoding=gbk", SPRING], SPRING_GBK)
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_utf16() -> TestResult {
+    run(&[BUSTLE_UTF16], "tests/expected/the-bustle.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_utf16_stdin_tail() -> TestResult {
+    run_stdin(
+        BUSTLE_UTF16,
+        &["--tail", "3"],
+        "tests/expected/the-bustle.txt.tail.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn cafe_latin1() -> TestResult {
+    run(
+        &["--from-encoding=latin1", CAFE_LATIN1],
+        "tests/expected/cafe.txt.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn strip_bom() -> TestResult {
+    let input = temp_path("bom.txt");
+    fs::write(&input, b"\xef\xbb\xbfhello\n")?;
+    Command::cargo_bin(PRG)?
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout("\u{feff}hello\n");
+    Command::cargo_bin(PRG)?
+        .args(["--strip-bom", "-n"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout("     1\thello\n");
+    fs::remove_file(&input)?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_encoding() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--from-encoding", "klingon", FOX])
+        .assert()
+        .failure()
+        .code(2)
+        .stderr("unknown encoding -- klingon\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_recursive() -> TestResult {
+    run(&["-r", TREE], "tests/expected/tree.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_skips_hidden() -> TestResult {
+    let dir = temp_path("tree");
+    fs::create_dir_all(dir.join(".git/objects"))?;
+    fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n")?;
+    fs::write(dir.join(".git/objects/ab"), "blob\n")?;
+    fs::write(dir.join(".env"), "SECRET=1\n")?;
+    fs::write(dir.join("a.txt"), "visible\n")?;
+    let result = Command::cargo_bin(PRG)?.arg("-r").arg(&dir).assert();
+    fs::remove_dir_all(&dir)?;
+    result.success().stdout("visible\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_recursive_headers() -> TestResult {
+    run(&["-rH", TREE], "tests/expected/tree.r.H.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tree_glob() -> TestResult {
+    run(
+        &["--headers", "tests/inputs/tree/**/*.conf"],
+        "tests/expected/tree.r.H.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unmatched_glob() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/*.nope", FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("The quick brown fox jumps over the lazy dog.\n")
+        .stderr(predicate::str::starts_with("tests/inputs/*.nope: "));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_grep_context() -> TestResult {
+    run(
+        &["-n", "-i", "--grep", "LOVE|bustle", "-C", "1", BUSTLE],
+        "tests/expected/the-bustle.txt.grep.n.C1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_grep_exclude() -> TestResult {
+    run(
+        &["--grep=The", "--exclude=sweeping", BUSTLE],
+        "tests/expected/the-bustle.txt.grep.exclude.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_pattern() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--grep", "(", FOX])
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::starts_with("invalid pattern -- "));
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! # 使用Rust编写的kill命令
+//!
+//! 信号名的解析和发送都来自 ps 库的 [`ps::signal`] 模块，和 pkill、
+//! ps --top 使用同一张信号表。
+
+use ps::{signal, PsError};
+use std::io::{self, Write};
+
+pub const USAGE: &str = "\
+用法:
+  kill [-s SIGNAL | -SIGNAL] PID...
+  kill -l [SIGNAL]
+
+  -s, --signal SIGNAL  要发送的信号，可以是名字（TERM、SIGTERM）或编号
+  -SIGNAL              同 -s，例如 -9、-KILL
+  -l, --list [SIGNAL]  列出所有信号名，或在信号名和编号之间转换
+  --help               显示帮助
+
+默认发送 TERM。负数的 PID 表示进程组，需要写在 -- 之后或信号之后";
+
+/// 解析后的命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// kill -l，可以带一个信号名或编号
+    List(Option<String>),
+    Send {
+        signal: libc::c_int,
+        pids: Vec<i32>,
+    },
+    Help,
+}
+
+// --------------------------------------------------
+pub fn parse(args: &[String]) -> Result<Command, PsError> {
+    let mut signal = None;
+    let mut pids = vec![];
+    let mut options_done = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // 给出信号或第一个 PID 之后，-123 只能是进程组
+        let negative_pid = arg
+            .strip_prefix('-')
+            .is_some_and(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))
+            && (signal.is_some() || !pids.is_empty());
+        if options_done || !arg.starts_with('-') || arg == "-" || negative_pid {
+            pids.push(parse_pid(arg)?);
+            continue;
+        }
+        match arg.as_str() {
+            "--" => options_done = true,
+            "--help" => return Ok(Command::Help),
+            "-l" | "--list" | "-L" => return Ok(Command::List(args.next().cloned())),
+            "-s" | "--signal" | "-n" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| usage(format!("{} 需要一个信号", arg)))?;
+                signal = Some(parse_signal(name)?);
+            }
+            _ => {
+                let name = arg.strip_prefix("--signal=").unwrap_or(&arg[1..]);
+                signal = Some(parse_signal(name)?);
+            }
+        }
+    }
+    if pids.is_empty() {
+        return Err(usage("没有指定进程".to_string()));
+    }
+    Ok(Command::Send {
+        signal: signal.unwrap_or(libc::SIGTERM),
+        pids,
+    })
+}
+
+// --------------------------------------------------
+fn usage(msg: String) -> PsError {
+    PsError::Usage(msg)
+}
+
+// --------------------------------------------------
+fn parse_signal(name: &str) -> Result<libc::c_int, PsError> {
+    signal::parse(name).ok_or_else(|| usage(format!("未知的信号 {}", name)))
+}
+
+// --------------------------------------------------
+fn parse_pid(pid: &str) -> Result<i32, PsError> {
+    pid.parse()
+        .map_err(|_| usage(format!("无效的进程号 {}", pid)))
+}
+
+// --------------------------------------------------
+/// kill -l SIGNAL：编号转成名字，名字转成编号
+fn translate(name: &str) -> Result<String, PsError> {
+    let number = parse_signal(name)?;
+    if name.parse::<libc::c_int>().is_ok() {
+        signal::name(number)
+            .map(str::to_string)
+            .ok_or_else(|| usage(format!("信号 {} 没有名字", number)))
+    } else {
+        Ok(number.to_string())
+    }
+}
+
+// --------------------------------------------------
+/// 主流程。所有进程都发送成功时返回 true，失败的进程逐个报告
+pub fn run(args: &[String]) -> Result<bool, PsError> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match parse(args)? {
+        Command::Help => writeln!(out, "{}", USAGE)?,
+        Command::List(None) => writeln!(out, "{}", signal::list())?,
+        Command::List(Some(name)) => writeln!(out, "{}", translate(&name)?)?,
+        Command::Send { signal, pids } => {
+            let mut ok = true;
+            for pid in pids {
+                if let Err(e) = signal::send(pid, signal) {
+                    eprintln!("kill: ({}) - {}", pid, e);
+                    ok = false;
+                }
+            }
+            return Ok(ok);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Command, PsError> {
+        let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        parse(&args)
+    }
+
+    fn send(signal: libc::c_int, pids: &[i32]) -> Command {
+        Command::Send {
+            signal,
+            pids: pids.to_vec(),
+        }
+    }
+
+    #[test]
+    fn parses_signal_forms() {
+        assert_eq!(parse_str("42").unwrap(), send(libc::SIGTERM, &[42]));
+        assert_eq!(parse_str("-9 1 2").unwrap(), send(libc::SIGKILL, &[1, 2]));
+        assert_eq!(parse_str("-HUP 7").unwrap(), send(libc::SIGHUP, &[7]));
+        assert_eq!(parse_str("-s sigint 7").unwrap(), send(libc::SIGINT, &[7]));
+        assert_eq!(
+            parse_str("--signal=USR1 7").unwrap(),
+            send(libc::SIGUSR1, &[7])
+        );
+        // 信号之后的负数是进程组
+        assert_eq!(parse_str("-9 -123").unwrap(), send(libc::SIGKILL, &[-123]));
+        assert_eq!(parse_str("-- -123").unwrap(), send(libc::SIGTERM, &[-123]));
+    }
+
+    #[test]
+    fn lists_signals() {
+        assert_eq!(parse_str("-l").unwrap(), Command::List(None));
+        assert_eq!(
+            parse_str("-l 9").unwrap(),
+            Command::List(Some("9".to_string()))
+        );
+        assert_eq!(translate("9").unwrap(), "KILL");
+        assert_eq!(translate("SIGTERM").unwrap(), "15");
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(matches!(parse_str(""), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-BOGUS 1"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("abc"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-s"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("ä"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-9 ä"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-ä 1"), Err(PsError::Usage(_))));
+    }
+}
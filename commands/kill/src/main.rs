@@ -0,0 +1,16 @@
+// 信号的解析和发送在 ps 库中，具体实现见 lib.rs
+
+use ps::PsError;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match kill::run(&args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(PsError::Io(e)) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("kill: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+// pkill 和 pgrep 共用同一套参数和匹配逻辑，见 lib.rs
+
+fn main() {
+    std::process::exit(pgrep::main(pgrep::Mode::Pkill));
+}
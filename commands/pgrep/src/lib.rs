@@ -0,0 +1,364 @@
+//! # 使用Rust编写的pgrep和pkill命令
+//!
+//! 进程从 /proc 读取，匹配使用 ps 库的 [`Matcher`]，信号使用
+//! [`ps::signal`]。pgrep 打印匹配的进程号，pkill 向它们发送信号。
+
+use ps::args::{parse_list, parse_pids, parse_users, strip_dev};
+use ps::{signal, Matcher, PsError, Users};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// 以哪个命令运行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Pgrep,
+    Pkill,
+}
+
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Pgrep => "pgrep",
+            Mode::Pkill => "pkill",
+        }
+    }
+
+    fn usage(&self) -> String {
+        let (extra, signal) = match self {
+            Mode::Pgrep => (
+                "  -l, --list-name      同时显示进程名
+  -a, --list-full      同时显示完整命令行
+  -d, --delimiter STR  进程号之间的分隔符，默认为换行
+  -c, --count          只显示匹配的进程数
+",
+                "",
+            ),
+            Mode::Pkill => (
+                "  -e, --echo           显示被发送信号的进程
+  -c, --count          显示成功收到信号的进程数
+",
+                " [-SIGNAL | --signal SIGNAL]",
+            ),
+        };
+        format!(
+            "用法: {name}{signal} [选项] [PATTERN]
+
+PATTERN 是匹配进程名的正则表达式，所有条件必须同时满足。
+
+  -f, --full           匹配完整命令行，而不只是进程名
+  -x, --exact          要求匹配整个进程名或命令行
+  -i, --ignore-case    忽略大小写
+  -u, --euid USERS     有效用户，用逗号分隔
+  -P, --parent PPIDS   父进程号，用逗号分隔
+  -t, --terminal TTYS  终端，例如 pts/0
+  -n, --newest         只选最新启动的进程
+  -o, --oldest         只选最早启动的进程
+  -v, --inverse        反转匹配结果
+{extra}  --help               显示帮助
+
+退出码: 0 有匹配的进程，1 没有，2 参数错误，3 其他错误",
+            name = self.name(),
+            signal = signal,
+            extra = extra,
+        )
+    }
+}
+
+/// 解析后的命令行
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub matcher: Matcher,
+    /// pkill 发送的信号
+    pub signal: libc::c_int,
+    pub list_name: bool,
+    pub list_full: bool,
+    pub delimiter: String,
+    pub count: bool,
+    pub echo: bool,
+    pub help: bool,
+}
+
+// --------------------------------------------------
+/// 解析命令行参数（不含程序名）
+pub fn parse(mode: Mode, args: &[String], users: &Users) -> Result<Options, PsError> {
+    let mut options = Options {
+        matcher: Matcher::default(),
+        signal: libc::SIGTERM,
+        list_name: false,
+        list_full: false,
+        delimiter: "\n".to_string(),
+        count: false,
+        echo: false,
+        help: false,
+    };
+    let mut pattern: Option<String> = None;
+    let mut exact = false;
+    let mut ignore_case = false;
+    let mut has_criteria = false;
+
+    let mut args = args.iter().enumerate();
+    while let Some((index, arg)) = args.next() {
+        // 和 procps 一样，pkill 的信号只能作为第一个参数
+        if mode == Mode::Pkill && index == 0 {
+            if let Some(number) = arg.strip_prefix('-').and_then(signal::parse) {
+                options.signal = number;
+                continue;
+            }
+        }
+        let (flags, attached) = if let Some(long) = arg.strip_prefix("--") {
+            let (name, attached) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let flag = match (name, mode) {
+                ("full", _) => 'f',
+                ("exact", _) => 'x',
+                ("ignore-case", _) => 'i',
+                ("euid", _) => 'u',
+                ("parent", _) => 'P',
+                ("terminal", _) => 't',
+                ("newest", _) => 'n',
+                ("oldest", _) => 'o',
+                ("inverse", _) => 'v',
+                ("count", _) => 'c',
+                ("list-name", Mode::Pgrep) => 'l',
+                ("list-full", Mode::Pgrep) => 'a',
+                ("delimiter", Mode::Pgrep) => 'd',
+                ("echo", Mode::Pkill) => 'e',
+                ("signal", Mode::Pkill) => 'S',
+                ("help", _) => 'h',
+                _ => return Err(usage(format!("未知选项 --{}", name))),
+            };
+            (flag.to_string(), attached)
+        } else if let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) {
+            (flags.to_string(), None)
+        } else {
+            if pattern.is_some() {
+                return Err(usage(format!("只能给出一个模式，多余的参数 {}", arg)));
+            }
+            pattern = Some(arg.clone());
+            continue;
+        };
+
+        for (i, flag) in flags.char_indices() {
+            // S 和 h 只是 --signal 和 --help 在下面的内部表示，不能作为短选项
+            if !arg.starts_with("--") && matches!(flag, 'S' | 'h') {
+                return Err(usage(format!("未知选项 -{}", flag)));
+            }
+            // -u root 和 -uroot 两种写法都支持
+            let rest = &flags[i + flag.len_utf8()..];
+            let mut value = || match (&attached, rest.is_empty()) {
+                (Some(value), _) => Ok(value.clone()),
+                (None, false) => Ok(rest.to_string()),
+                (None, true) => args
+                    .next()
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| usage(format!("-{} 需要一个参数", flag))),
+            };
+            match (flag, mode) {
+                ('f', _) => options.matcher.full = true,
+                ('x', _) => exact = true,
+                ('i', _) => ignore_case = true,
+                ('n', _) => options.matcher.newest = true,
+                ('o', _) => options.matcher.oldest = true,
+                ('v', _) => options.matcher.inverse = true,
+                ('c', _) => options.count = true,
+                ('h', _) => options.help = true,
+                ('l', Mode::Pgrep) => options.list_name = true,
+                ('a', Mode::Pgrep) => options.list_full = true,
+                ('e', Mode::Pkill) => options.echo = true,
+                ('u', _) => {
+                    let uids = parse_users(&value()?, users)?;
+                    options.matcher.selection.uids.extend(uids);
+                    has_criteria = true;
+                }
+                ('P', _) => {
+                    let ppids = parse_pids(&value()?)?;
+                    options.matcher.selection.ppids.extend(ppids);
+                    has_criteria = true;
+                }
+                ('t', _) => {
+                    let ttys = value()?;
+                    let ttys = parse_list(&ttys).map(strip_dev);
+                    options.matcher.selection.ttys.extend(ttys);
+                    has_criteria = true;
+                }
+                ('d', Mode::Pgrep) => options.delimiter = value()?,
+                ('S', Mode::Pkill) => {
+                    let name = value()?;
+                    options.signal = signal::parse(&name)
+                        .ok_or_else(|| usage(format!("未知的信号 {}", name)))?;
+                }
+                _ => return Err(usage(format!("未知选项 -{}", flag))),
+            }
+            if matches!(flag, 'u' | 'P' | 't' | 'd' | 'S') {
+                break;
+            }
+        }
+    }
+
+    if options.matcher.newest && options.matcher.oldest {
+        return Err(usage("-n 和 -o 不能同时使用".to_string()));
+    }
+    if let Some(pattern) = pattern {
+        options.matcher.pattern = Some(Matcher::compile(&pattern, exact, ignore_case)?);
+    } else if !has_criteria && !options.help {
+        return Err(usage("没有指定匹配条件".to_string()));
+    }
+    Ok(options)
+}
+
+// --------------------------------------------------
+fn usage(msg: String) -> PsError {
+    PsError::Usage(msg)
+}
+
+// --------------------------------------------------
+/// 主流程。pgrep 有匹配的进程时返回 true；pkill 至少向一个进程成功发送
+/// 了信号时返回 true，全部失败（例如都是 EPERM）时和 procps 一样返回 false
+pub fn run(mode: Mode, args: &[String]) -> Result<bool, PsError> {
+    let users = Users::read(Path::new(ps::PASSWD));
+    let options = parse(mode, args, &users)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if options.help {
+        writeln!(out, "{}", mode.usage())?;
+        return Ok(true);
+    }
+
+    let source = ps::default_source();
+    let sys = source.system()?;
+    let processes = source.processes(&sys, &users)?;
+    let selected = options
+        .matcher
+        .select(&processes, std::process::id() as i32);
+
+    let mut found = !selected.is_empty();
+    match mode {
+        Mode::Pgrep if options.count => writeln!(out, "{}", selected.len())?,
+        Mode::Pgrep => {
+            let items: Vec<String> = selected
+                .iter()
+                .map(|process| {
+                    if options.list_full {
+                        format!("{} {}", process.pid, process.command())
+                    } else if options.list_name {
+                        format!("{} {}", process.pid, process.name)
+                    } else {
+                        process.pid.to_string()
+                    }
+                })
+                .collect();
+            if !items.is_empty() {
+                writeln!(out, "{}", items.join(&options.delimiter))?;
+            }
+        }
+        Mode::Pkill => {
+            let mut sent = 0;
+            for process in &selected {
+                match signal::send(process.pid, options.signal) {
+                    Ok(()) => {
+                        sent += 1;
+                        if options.echo {
+                            writeln!(out, "{} killed (pid {})", process.name, process.pid)?;
+                        }
+                    }
+                    Err(e) => eprintln!("pkill: killing pid {} failed: {}", process.pid, e),
+                }
+            }
+            found = sent > 0;
+            if options.count {
+                writeln!(out, "{}", sent)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(found)
+}
+
+// --------------------------------------------------
+/// 运行并返回退出码，pgrep 和 pkill 两个可执行文件共用
+pub fn main(mode: Mode) -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(mode, &args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        // 输出被 head 等提前关闭时安静退出
+        Err(PsError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(e @ PsError::Usage(_)) => {
+            eprintln!("{}: {}", mode.name(), e);
+            eprintln!("用 {} --help 查看用法", mode.name());
+            2
+        }
+        Err(e) => {
+            eprintln!("{}: {}", mode.name(), e);
+            3
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(mode: Mode, args: &str) -> Result<Options, PsError> {
+        let users = Users::parse("root:x:0:0::/root:/bin/sh\nalice:x:1000:1000::/:/bin/sh\n");
+        let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        parse(mode, &args, &users)
+    }
+
+    #[test]
+    fn parses_pgrep_options() {
+        let options = parse_str(Mode::Pgrep, "-fl -u alice -P1,2 -d, ^ssh").unwrap();
+        let matcher = &options.matcher;
+        assert!(matcher.full);
+        assert!(options.list_name);
+        assert_eq!(matcher.selection.uids, vec![1000]);
+        assert_eq!(matcher.selection.ppids, vec![1, 2]);
+        assert_eq!(options.delimiter, ",");
+        assert_eq!(matcher.pattern.as_ref().unwrap().as_str(), "^ssh");
+
+        let options = parse_str(Mode::Pgrep, "-xi --terminal=/dev/pts/1 SSHD").unwrap();
+        assert_eq!(options.matcher.selection.ttys, vec!["pts/1"]);
+        assert_eq!(
+            options.matcher.pattern.as_ref().unwrap().as_str(),
+            "^(?:SSHD)$"
+        );
+        // 只有 -u 也可以
+        assert!(parse_str(Mode::Pgrep, "-u root").is_ok());
+    }
+
+    #[test]
+    fn parses_pkill_signals() {
+        assert_eq!(
+            parse_str(Mode::Pkill, "sleep").unwrap().signal,
+            libc::SIGTERM
+        );
+        assert_eq!(
+            parse_str(Mode::Pkill, "-9 sleep").unwrap().signal,
+            libc::SIGKILL
+        );
+        assert_eq!(
+            parse_str(Mode::Pkill, "-HUP -f sleep").unwrap().signal,
+            libc::SIGHUP
+        );
+        let options = parse_str(Mode::Pkill, "--signal USR1 -e sleep").unwrap();
+        assert_eq!(options.signal, libc::SIGUSR1);
+        assert!(options.echo);
+        // 信号只能是第一个参数
+        assert!(parse_str(Mode::Pkill, "sleep -9").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let usage_error = |mode, args| matches!(parse_str(mode, args), Err(PsError::Usage(_)));
+        assert!(usage_error(Mode::Pgrep, ""));
+        assert!(usage_error(Mode::Pgrep, "a b"));
+        assert!(usage_error(Mode::Pgrep, "-n -o sshd"));
+        assert!(usage_error(Mode::Pgrep, "-e sshd"));
+        assert!(usage_error(Mode::Pkill, "-l sshd"));
+        assert!(usage_error(Mode::Pkill, "-e -STOP sshd"));
+        assert!(usage_error(Mode::Pgrep, "("));
+        assert!(usage_error(Mode::Pgrep, "-u nobody"));
+    }
+}
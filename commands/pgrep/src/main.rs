@@ -0,0 +1,5 @@
+// 进程匹配使用 ps 库的 Matcher，具体实现见 lib.rs
+
+fn main() {
+    std::process::exit(pgrep::main(pgrep::Mode::Pgrep));
+}
@@ -0,0 +1,612 @@
+//! 手写的命令行解析，同时支持 BSD 语法（aux）和 POSIX 语法（-ef）
+//!
+//! clap 无法处理不带 '-' 的 BSD 选项，所以这里逐个字符解析。
+
+use crate::error::PsError;
+use crate::export::OutputFormat;
+use crate::format::{Column, Field, Layout, SortKey};
+use crate::group::GroupBy;
+use crate::sample::parse_duration;
+use crate::select::Selection;
+use crate::tree::PstreeOptions;
+use crate::users::Users;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+用法: ps [选项]
+
+BSD 语法:
+  a          显示所有用户的进程
+  u          面向用户的格式 (USER PID %CPU %MEM ...)
+  x          包括没有终端的进程
+  f          同 --forest
+  U USER     按用户选择
+  p PID      按进程号选择
+  t TTY      按终端选择
+
+POSIX 语法:
+  -e, -A     所有进程
+  -f         完整格式 (UID PID PPID C STIME TTY TIME CMD)
+  -o LIST    自定义输出列，例如 -o pid,user,%cpu,args 或 -o pid=ID
+  -u USER    按有效用户选择
+  -p PID     按进程号选择
+  -C NAME    按进程名选择
+  -t TTY     按终端选择
+  -L         每个线程一行，加上 LWP 列（线程号），CMD 为线程名。线程的
+             状态和最近运行所在的 CPU 可以用 -o 加上，例如
+             -L -o pid,tid,comm,stat,psr,time；--sample 按线程采样
+  -T         同 -L，线程号列的表头为 SPID
+  --ppid PID 按父进程号选择
+  --sort KEYS
+             按列排序，例如 --sort=-%cpu,pid（- 表示降序）
+  --no-headers
+             不打印表头
+  --sample TIME
+             间隔 TIME（例如 500ms、1s）读两次 /proc，%CPU 为这段时间内的
+             占用率，表头显示为 %CPU(500ms)。默认的 %CPU 是进程整个生命
+             周期的平均值，和 procps 相同
+  --forest   按进程树排列，命令前用 \\_ 缩进
+  --pstree[=PID]
+             画出 pstree 风格的进程树，可以只画以 PID 为根的子树
+  --show-pids
+             --pstree 中显示进程号和线程号
+  --highlight PID
+             --pstree 中加粗显示 PID 及其所有祖先
+  --format table|json|csv
+             输出格式。json 和 csv 输出每个进程的完整记录（命令行、cwd、
+             exe、启动时间等），不受 -o 影响
+  --unit NAME
+             只显示属于 systemd unit NAME 的进程（包括其中的子 cgroup），
+             .service 后缀可以省略
+  --group cgroup|unit
+             按 cgroup 或 systemd unit 汇总进程数、%CPU、%MEM 和 RSS，
+             可以和 --sample、--format 一起使用
+  --record FILE
+             每隔 --interval（默认 1s）把所有进程的快照写入 FILE，直到按
+             Ctrl-C。进程的命令行等信息只写一次，之后每帧只写变化的计数
+  --replay FILE
+             读取 --record 的记录，输出每个进程的 %CPU 和 RSS 峰值；
+             加上 --top 时按记录的间隔（或 --interval）逐帧回放
+  --interval TIME
+             --record 的记录间隔和 --replay --top 的播放间隔
+  --inspect PID
+             显示单个进程的详细报告：打开的文件（socket 解析为地址）、内存
+             映射汇总、环境变量、资源限制、cgroup、命名空间和 I/O 计数
+  --top      类似 top 的交互界面，刷新间隔由 --sample 指定（默认 1s），
+             -o 可以指定显示的列
+  --help     显示帮助
+
+列表参数可以用逗号分隔，例如 -p 1,2,3
+
+-o 和 --sort 可用的列: pid ppid user uid %cpu c %mem vsz rss tty stat
+start stime time bsdtime nlwp lwp(tid、spid) psr comm args cgroup unit";
+
+/// 解析后的命令行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub selection: Selection,
+    pub layout: Layout,
+    /// -o 指定的列，为 None 时使用 layout 的标准列
+    pub fields: Option<Vec<Field>>,
+    /// -L、-T 时线程号列及其表头
+    pub threads: Option<Field>,
+    pub sort: Vec<SortKey>,
+    pub headers: bool,
+    /// --sample 的采样间隔
+    pub sample: Option<Duration>,
+    pub forest: bool,
+    /// --pstree 模式
+    pub pstree: Option<PstreeOptions>,
+    pub top: bool,
+    /// --group 的汇总方式
+    pub group: Option<GroupBy>,
+    /// --record 的记录文件
+    pub record: Option<PathBuf>,
+    /// --replay 的记录文件
+    pub replay: Option<PathBuf>,
+    /// --record 和 --replay 的间隔
+    pub interval: Option<Duration>,
+    /// --inspect 的进程号
+    pub inspect: Option<i32>,
+    pub output: OutputFormat,
+    pub help: bool,
+}
+
+impl Options {
+    /// 实际输出的列
+    pub fn fields(&self) -> Vec<Field> {
+        match (&self.fields, &self.threads) {
+            (Some(fields), _) => fields.clone(),
+            (None, Some(lwp)) => self.layout.thread_fields(lwp),
+            (None, None) => self.layout.fields(),
+        }
+    }
+}
+
+// --------------------------------------------------
+/// 解析命令行参数（不含程序名）
+pub fn parse(args: &[String], users: &Users) -> Result<Options, PsError> {
+    let mut selection = Selection::default();
+    let mut full = false;
+    let mut bsd_user = false;
+    let mut fields: Option<Vec<Field>> = None;
+    let mut threads = None;
+    let mut sort = vec![];
+    let mut headers = true;
+    let mut sample = None;
+    let mut forest = false;
+    let mut pstree: Option<PstreeOptions> = None;
+    let mut top = false;
+    let mut group = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut interval = None;
+    let mut inspect = None;
+    let mut output = OutputFormat::Table;
+    let mut help = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, attached) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let mut value = || {
+                attached
+                    .clone()
+                    .or_else(|| args.next().cloned())
+                    .ok_or_else(|| usage(format!("--{} 需要一个参数", name)))
+            };
+            match name {
+                "help" => help = true,
+                "forest" => forest = true,
+                "top" => top = true,
+                "group" => {
+                    let name = value()?;
+                    group = Some(
+                        GroupBy::from_name(&name)
+                            .ok_or_else(|| usage(format!("未知的汇总方式 {}", name)))?,
+                    );
+                }
+                "record" => record = Some(PathBuf::from(value()?)),
+                "replay" => replay = Some(PathBuf::from(value()?)),
+                "interval" => {
+                    let text = value()?;
+                    let duration = parse_duration(&text)
+                        .filter(|duration| !duration.is_zero())
+                        .ok_or_else(|| usage(format!("无效的间隔 {}", text)))?;
+                    interval = Some(duration);
+                }
+                "inspect" => inspect = Some(parse_pid(&value()?)?),
+                "format" => {
+                    let name = value()?;
+                    output = OutputFormat::from_name(&name)
+                        .ok_or_else(|| usage(format!("未知的输出格式 {}", name)))?;
+                }
+                // --pstree 的参数是可选的，只能用 --pstree=PID 的形式给出
+                "pstree" => {
+                    let tree = pstree.get_or_insert_with(PstreeOptions::default);
+                    if let Some(pid) = &attached {
+                        tree.root = Some(parse_pid(pid)?);
+                    }
+                }
+                "show-pids" => pstree.get_or_insert_with(PstreeOptions::default).pids = true,
+                "highlight" => {
+                    let pid = parse_pid(&value()?)?;
+                    pstree.get_or_insert_with(PstreeOptions::default).highlight = Some(pid);
+                }
+                "no-headers" | "no-heading" => headers = false,
+                "sort" => sort.extend(parse_sort(&value()?)?),
+                "sample" => {
+                    let text = value()?;
+                    let interval = parse_duration(&text)
+                        .ok_or_else(|| usage(format!("无效的采样间隔 {}", text)))?;
+                    sample = Some(interval);
+                }
+                "ppid" => selection.ppids.extend(parse_pids(&value()?)?),
+                "pid" => selection.pids.extend(parse_pids(&value()?)?),
+                "user" => selection.uids.extend(parse_users(&value()?, users)?),
+                "tty" => selection.ttys.extend(parse_list(&value()?).map(strip_dev)),
+                "unit" => selection.units.extend(parse_list(&value()?)),
+                _ => return Err(usage(format!("未知选项 --{}", name))),
+            }
+        } else if let Some(flags) = arg.strip_prefix('-') {
+            for (i, flag) in flags.char_indices() {
+                // -u root 和 -uroot 两种写法都支持
+                let rest = &flags[i + flag.len_utf8()..];
+                let mut value = || {
+                    if rest.is_empty() {
+                        args.next()
+                            .cloned()
+                            .ok_or_else(|| usage(format!("-{} 需要一个参数", flag)))
+                    } else {
+                        Ok(rest.to_string())
+                    }
+                };
+                match flag {
+                    'e' | 'A' => selection.all = true,
+                    'f' => full = true,
+                    'L' => threads = Some(Field::new(Column::Lwp)),
+                    'T' => threads = Some(Field::with_header(Column::Lwp, "SPID")),
+                    'o' => fields
+                        .get_or_insert_with(Vec::new)
+                        .extend(parse_fields(&value()?)?),
+                    'u' | 'U' => selection.uids.extend(parse_users(&value()?, users)?),
+                    'p' => selection.pids.extend(parse_pids(&value()?)?),
+                    'C' => selection.commands.extend(parse_list(&value()?)),
+                    't' => selection.ttys.extend(parse_list(&value()?).map(strip_dev)),
+                    _ => return Err(usage(format!("未知选项 -{}", flag))),
+                }
+                if matches!(flag, 'u' | 'U' | 'p' | 'C' | 't' | 'o') {
+                    break;
+                }
+            }
+        } else {
+            selection.bsd = true;
+            for (i, flag) in arg.char_indices() {
+                let rest = &arg[i + flag.len_utf8()..];
+                let mut value = || {
+                    if rest.is_empty() {
+                        args.next()
+                            .cloned()
+                            .ok_or_else(|| usage(format!("{} 需要一个参数", flag)))
+                    } else {
+                        Ok(rest.to_string())
+                    }
+                };
+                match flag {
+                    'a' => selection.any_user = true,
+                    'x' => selection.no_tty = true,
+                    'u' => bsd_user = true,
+                    // 宽输出：本实现从不截断命令行
+                    'w' => {}
+                    'f' => forest = true,
+                    'o' => fields
+                        .get_or_insert_with(Vec::new)
+                        .extend(parse_fields(&value()?)?),
+                    'U' => selection.uids.extend(parse_users(&value()?, users)?),
+                    'p' => selection.pids.extend(parse_pids(&value()?)?),
+                    't' => selection.ttys.extend(parse_list(&value()?).map(strip_dev)),
+                    _ => return Err(usage(format!("未知的 BSD 选项 {}", flag))),
+                }
+                if matches!(flag, 'U' | 'p' | 't' | 'o') {
+                    break;
+                }
+            }
+        }
+    }
+    if selection.any_user && selection.no_tty {
+        selection.all = true;
+    }
+    // 输出到管道或文件时不写控制字符
+    if let Some(tree) = &mut pstree {
+        tree.bold = tree.highlight.is_some() && io::stdout().is_terminal();
+    }
+
+    let layout = if bsd_user {
+        Layout::BsdUser
+    } else if full {
+        Layout::Full
+    } else if selection.bsd {
+        Layout::Bsd
+    } else {
+        Layout::Default
+    };
+    Ok(Options {
+        selection,
+        layout,
+        fields,
+        threads,
+        sort,
+        headers,
+        sample,
+        forest,
+        pstree,
+        top,
+        group,
+        record,
+        replay,
+        interval,
+        inspect,
+        output,
+        help,
+    })
+}
+
+// --------------------------------------------------
+fn usage(msg: String) -> PsError {
+    PsError::Usage(msg)
+}
+
+// --------------------------------------------------
+/// 列表可以用逗号或空格分隔
+pub fn parse_list(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.split([',', ' '])
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+}
+
+// --------------------------------------------------
+pub fn parse_pids(list: &str) -> Result<Vec<i32>, PsError> {
+    parse_list(list).map(|pid| parse_pid(&pid)).collect()
+}
+
+// --------------------------------------------------
+pub fn parse_pid(pid: &str) -> Result<i32, PsError> {
+    match pid.parse() {
+        Ok(pid) if pid > 0 => Ok(pid),
+        _ => Err(usage(format!("无效的进程号 {}", pid))),
+    }
+}
+
+// --------------------------------------------------
+pub fn parse_users(list: &str, users: &Users) -> Result<Vec<u32>, PsError> {
+    parse_list(list)
+        .map(|user| {
+            users
+                .uid(&user)
+                .ok_or_else(|| usage(format!("未知用户 {}", user)))
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+/// 解析 -o 的列表。和 procps 一样，"=" 之后直到参数结尾都是表头，所以
+/// -o pid,args=COMMAND LINE 的表头可以包含空格和逗号
+fn parse_fields(spec: &str) -> Result<Vec<Field>, PsError> {
+    let mut fields = vec![];
+    let mut rest = spec;
+    while !rest.is_empty() {
+        let end = rest.find([',', ' ', '=']).unwrap_or(rest.len());
+        let name = &rest[..end];
+        let column = column(name)?;
+        match rest[end..].chars().next() {
+            Some('=') => {
+                fields.push(Field::with_header(column, &rest[end + 1..]));
+                break;
+            }
+            Some(_) => {
+                fields.push(Field::new(column));
+                rest = &rest[end + 1..];
+            }
+            None => {
+                fields.push(Field::new(column));
+                break;
+            }
+        }
+    }
+    if fields.is_empty() {
+        return Err(usage("-o 需要至少一列".to_string()));
+    }
+    Ok(fields)
+}
+
+// --------------------------------------------------
+/// 解析 --sort 的键，前缀 - 表示降序，+ 或没有前缀表示升序
+fn parse_sort(spec: &str) -> Result<Vec<SortKey>, PsError> {
+    parse_list(spec)
+        .map(|key| {
+            let (descending, name) = match key.strip_prefix('-') {
+                Some(name) => (true, name),
+                None => (false, key.strip_prefix('+').unwrap_or(&key)),
+            };
+            Ok(SortKey {
+                column: column(name)?,
+                descending,
+            })
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+fn column(name: &str) -> Result<Column, PsError> {
+    Column::from_name(&name.to_lowercase()).ok_or_else(|| usage(format!("未知的列 {}", name)))
+}
+
+// --------------------------------------------------
+/// -t 和 --tty 接受 pts/0，也接受带 /dev/ 前缀的写法
+pub fn strip_dev(name: String) -> String {
+    name.strip_prefix("/dev/")
+        .map(str::to_string)
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &str) -> Result<Options, PsError> {
+        let users = Users::parse("root:x:0:0::/root:/bin/sh\nalice:x:1000:1000::/:/bin/sh\n");
+        let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        parse(&args, &users)
+    }
+
+    #[test]
+    fn parses_bsd_aux() {
+        let options = parse_str("aux").unwrap();
+        assert!(options.selection.all);
+        assert!(options.selection.bsd);
+        assert_eq!(options.layout, Layout::BsdUser);
+
+        let options = parse_str("ax").unwrap();
+        assert!(options.selection.all);
+        assert_eq!(options.layout, Layout::Bsd);
+    }
+
+    #[test]
+    fn parses_posix_ef() {
+        let options = parse_str("-ef").unwrap();
+        assert!(options.selection.all);
+        assert!(!options.selection.bsd);
+        assert_eq!(options.layout, Layout::Full);
+
+        let options = parse_str("-e -f").unwrap();
+        assert_eq!(options.layout, Layout::Full);
+    }
+
+    #[test]
+    fn parses_lists() {
+        let options = parse_str("-u alice,0 -p 1,2 -C bash -t /dev/pts/3 --ppid 7").unwrap();
+        let selection = options.selection;
+        assert_eq!(selection.uids, vec![1000, 0]);
+        assert_eq!(selection.pids, vec![1, 2]);
+        assert_eq!(selection.commands, vec!["bash"]);
+        assert_eq!(selection.ttys, vec!["pts/3"]);
+        assert_eq!(selection.ppids, vec![7]);
+        assert_eq!(options.layout, Layout::Default);
+
+        let options = parse_str("-uroot -p42 --ppid=1").unwrap();
+        assert_eq!(options.selection.uids, vec![0]);
+        assert_eq!(options.selection.pids, vec![42]);
+        assert_eq!(options.selection.ppids, vec![1]);
+
+        let options = parse_str("--tty /dev/pts/0,tty1").unwrap();
+        assert_eq!(options.selection.ttys, vec!["pts/0", "tty1"]);
+    }
+
+    #[test]
+    fn parses_output_fields() {
+        let options = parse_str("-o pid,%cpu -o comm=NAME").unwrap();
+        assert_eq!(
+            options.fields(),
+            vec![
+                Field::new(Column::Pid),
+                Field::new(Column::Cpu),
+                Field::with_header(Column::Comm, "NAME"),
+            ]
+        );
+
+        // 表头延续到参数结尾，可以包含逗号
+        let options = parse_str("-opid=A,B").unwrap();
+        assert_eq!(
+            options.fields(),
+            vec![Field::with_header(Column::Pid, "A,B")]
+        );
+
+        let options = parse_str("aux").unwrap();
+        assert_eq!(options.fields(), Layout::BsdUser.fields());
+        assert!(matches!(parse_str("-o pid,bogus"), Err(PsError::Usage(_))));
+    }
+
+    #[test]
+    fn parses_sort_keys() {
+        let options = parse_str("--sort=-%cpu,+pid --no-headers").unwrap();
+        assert_eq!(
+            options.sort,
+            vec![
+                SortKey {
+                    column: Column::Cpu,
+                    descending: true
+                },
+                SortKey {
+                    column: Column::Pid,
+                    descending: false
+                },
+            ]
+        );
+        assert!(!options.headers);
+        assert_eq!(options.sample, None);
+
+        let options = parse_str("aux --sample=250ms").unwrap();
+        assert_eq!(options.sample, Some(Duration::from_millis(250)));
+        assert!(matches!(parse_str("--sample 0"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("--sort -bogus"), Err(PsError::Usage(_))));
+    }
+
+    #[test]
+    fn parses_tree_options() {
+        let options = parse_str("axf").unwrap();
+        assert!(options.forest);
+        assert_eq!(options.pstree, None);
+
+        let options = parse_str("--pstree=42 --show-pids --highlight 7").unwrap();
+        let tree = options.pstree.unwrap();
+        assert_eq!(tree.root, Some(42));
+        assert!(tree.pids);
+        assert_eq!(tree.highlight, Some(7));
+        assert_eq!(
+            parse_str("--pstree").unwrap().pstree,
+            Some(PstreeOptions::default())
+        );
+    }
+
+    #[test]
+    fn parses_output_format() {
+        assert_eq!(parse_str("-e").unwrap().output, OutputFormat::Table);
+        assert_eq!(
+            parse_str("-e --format json").unwrap().output,
+            OutputFormat::Json
+        );
+        assert_eq!(parse_str("--format=csv").unwrap().output, OutputFormat::Csv);
+        assert!(matches!(parse_str("--format xml"), Err(PsError::Usage(_))));
+    }
+
+    #[test]
+    fn parses_units_and_groups() {
+        let options = parse_str("--unit sshd,cron.service --group unit").unwrap();
+        assert_eq!(options.selection.units, vec!["sshd", "cron.service"]);
+        assert_eq!(options.group, Some(GroupBy::Unit));
+        assert_eq!(
+            parse_str("-e --group=cgroup").unwrap().group,
+            Some(GroupBy::Cgroup)
+        );
+        assert_eq!(parse_str("-e").unwrap().group, None);
+        assert!(matches!(parse_str("--group user"), Err(PsError::Usage(_))));
+    }
+
+    #[test]
+    fn parses_recordings() {
+        let options = parse_str("--record /tmp/ps.log --interval 500ms").unwrap();
+        assert_eq!(options.record, Some(PathBuf::from("/tmp/ps.log")));
+        assert_eq!(options.interval, Some(Duration::from_millis(500)));
+        let options = parse_str("--replay=/tmp/ps.log --top").unwrap();
+        assert_eq!(options.replay, Some(PathBuf::from("/tmp/ps.log")));
+        assert!(options.top);
+        assert!(matches!(parse_str("--interval 0"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("--record"), Err(PsError::Usage(_))));
+    }
+
+    #[test]
+    fn parses_inspect() {
+        assert_eq!(parse_str("--inspect 42").unwrap().inspect, Some(42));
+        assert_eq!(parse_str("--inspect=1").unwrap().inspect, Some(1));
+        assert_eq!(parse_str("-e").unwrap().inspect, None);
+        assert!(matches!(parse_str("--inspect"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("--inspect x"), Err(PsError::Usage(_))));
+    }
+
+    #[test]
+    fn parses_threads() {
+        let options = parse_str("-eL").unwrap();
+        assert_eq!(options.threads, Some(Field::new(Column::Lwp)));
+        let headers: Vec<String> = options.fields().into_iter().map(|f| f.header).collect();
+        assert_eq!(headers, vec!["PID", "LWP", "TTY", "TIME", "CMD"]);
+        let headers: Vec<String> = parse_str("-eLf")
+            .unwrap()
+            .fields()
+            .into_iter()
+            .map(|f| f.header)
+            .collect();
+        assert_eq!(
+            headers,
+            vec!["UID", "PID", "PPID", "LWP", "C", "NLWP", "STIME", "TTY", "TIME", "CMD"]
+        );
+        let options = parse_str("-T -p 1").unwrap();
+        assert_eq!(options.fields()[1], Field::with_header(Column::Lwp, "SPID"));
+        let options = parse_str("-L -o pid,tid,psr").unwrap();
+        assert_eq!(options.fields().len(), 3);
+        assert_eq!(parse_str("-e").unwrap().threads, None);
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(matches!(parse_str("-Q"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("q"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-p"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-p abc"), Err(PsError::Usage(_))));
+        assert!(matches!(parse_str("-u nobody"), Err(PsError::Usage(_))));
+    }
+}
@@ -0,0 +1,36 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// ps 可能遇到的错误
+#[derive(Debug)]
+pub enum PsError {
+    /// 命令行参数有误
+    Usage(String),
+    /// 读取 /proc 或写输出时出错
+    Io(io::Error),
+}
+
+impl fmt::Display for PsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PsError::Usage(msg) => write!(f, "{}", msg),
+            PsError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for PsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PsError::Usage(_) => None,
+            PsError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for PsError {
+    fn from(e: io::Error) -> Self {
+        PsError::Io(e)
+    }
+}
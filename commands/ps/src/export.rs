@@ -0,0 +1,265 @@
+//! --format json|csv：给脚本用的机器可读输出
+
+use crate::format::Context;
+use crate::process::Process;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 对齐的表格，使用 -o 或标准列
+    Table,
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "table" => Some(OutputFormat::Table),
+            "json" => Some(OutputFormat::Json),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// 一个进程的完整记录，json 和 csv 使用同样的字段名
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Record {
+    pub pid: i32,
+    pub ppid: i32,
+    pub uid: u32,
+    pub user: String,
+    pub name: String,
+    /// 单字符状态，例如 R、S、Z
+    pub state: String,
+    /// 和 %CPU 列相同，--sample 时为采样值
+    pub cpu_percent: f64,
+    pub mem_percent: f64,
+    pub rss_kb: u64,
+    pub vsz_kb: u64,
+    pub threads: u64,
+    pub tty: Option<String>,
+    /// RFC 3339 格式的本地时间
+    pub start_time: String,
+    /// CPU 时间，单位秒
+    pub cpu_time: f64,
+    pub cmdline: Vec<String>,
+    pub cwd: Option<String>,
+    pub exe: Option<String>,
+    pub cgroup: Option<String>,
+    /// 所属的 systemd unit
+    pub unit: Option<String>,
+    /// -L、-T 时的线程号，进程的记录为 null
+    pub tid: Option<i32>,
+    /// 最近一次运行所在的 CPU
+    pub processor: Option<u32>,
+}
+
+impl Record {
+    pub fn new(process: &Process, ctx: &Context) -> Record {
+        let start: DateTime<Local> = process.start_time.into();
+        let path = |path: &Option<std::path::PathBuf>| {
+            path.as_deref()
+                .map(Path::to_string_lossy)
+                .map(|path| path.into_owned())
+        };
+        Record {
+            pid: process.pid,
+            ppid: process.ppid,
+            uid: process.uid,
+            user: process.user.clone(),
+            name: process.name.clone(),
+            state: process.state.code().to_string(),
+            cpu_percent: round(ctx.cpu_percent(process)),
+            mem_percent: round(process.rss as f64 * 100.0 / ctx.sys.mem_total.max(1) as f64),
+            rss_kb: process.rss,
+            vsz_kb: process.vsz,
+            threads: process.threads,
+            tty: process.tty(),
+            start_time: start.to_rfc3339(),
+            cpu_time: process.cpu_time(ctx.sys.clk_tck).as_secs_f64(),
+            cmdline: process.cmdline.clone(),
+            cwd: path(&process.cwd),
+            exe: path(&process.exe),
+            cgroup: process.cgroup.clone(),
+            unit: process.unit().map(str::to_string),
+            tid: process.tid,
+            processor: process.processor,
+        }
+    }
+}
+
+/// csv 不支持数组，命令行用空格连接，没有值的字段为空
+#[derive(Serialize)]
+struct CsvRecord<'a> {
+    pid: i32,
+    ppid: i32,
+    uid: u32,
+    user: &'a str,
+    name: &'a str,
+    state: &'a str,
+    cpu_percent: f64,
+    mem_percent: f64,
+    rss_kb: u64,
+    vsz_kb: u64,
+    threads: u64,
+    tty: &'a str,
+    start_time: &'a str,
+    cpu_time: f64,
+    cmdline: String,
+    cwd: &'a str,
+    exe: &'a str,
+    cgroup: &'a str,
+    unit: &'a str,
+    tid: Option<i32>,
+    processor: Option<u32>,
+}
+
+impl<'a> From<&'a Record> for CsvRecord<'a> {
+    fn from(record: &'a Record) -> Self {
+        CsvRecord {
+            pid: record.pid,
+            ppid: record.ppid,
+            uid: record.uid,
+            user: &record.user,
+            name: &record.name,
+            state: &record.state,
+            cpu_percent: record.cpu_percent,
+            mem_percent: record.mem_percent,
+            rss_kb: record.rss_kb,
+            vsz_kb: record.vsz_kb,
+            threads: record.threads,
+            tty: record.tty.as_deref().unwrap_or(""),
+            start_time: &record.start_time,
+            cpu_time: record.cpu_time,
+            cmdline: record.cmdline.join(" "),
+            cwd: record.cwd.as_deref().unwrap_or(""),
+            exe: record.exe.as_deref().unwrap_or(""),
+            cgroup: record.cgroup.as_deref().unwrap_or(""),
+            unit: record.unit.as_deref().unwrap_or(""),
+            tid: record.tid,
+            processor: record.processor,
+        }
+    }
+}
+
+// --------------------------------------------------
+/// 百分比保留一位小数，和表格一致
+fn round(percent: f64) -> f64 {
+    (percent * 10.0).round() / 10.0
+}
+
+// --------------------------------------------------
+/// 所有进程输出为一个 json 数组
+pub fn write_json(out: &mut impl Write, processes: &[Process], ctx: &Context) -> io::Result<()> {
+    let records: Vec<Record> = processes.iter().map(|p| Record::new(p, ctx)).collect();
+    serde_json::to_writer_pretty(&mut *out, &records)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+// --------------------------------------------------
+/// 每个进程一行，headers 为 false 时不输出表头行
+pub fn write_csv(
+    out: &mut impl Write,
+    processes: &[Process],
+    ctx: &Context,
+    headers: bool,
+) -> io::Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(headers)
+        .from_writer(out);
+    for process in processes {
+        let record = Record::new(process, ctx);
+        writer.serialize(CsvRecord::from(&record))?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::SystemInfo;
+    use crate::sample::CpuMode;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn process() -> Process {
+        Process {
+            utime: 150,
+            stime: 50,
+            rss: 250,
+            vsz: 1000,
+            tty_nr: 0x8801,
+            cmdline: vec!["sleep".to_string(), "a, \"b\"".to_string()],
+            cwd: Some(PathBuf::from("/tmp")),
+            cgroup: Some("/system.slice/cron.service".to_string()),
+            ..Process::fixture(42, "sleep")
+        }
+    }
+
+    fn context() -> Context {
+        Context {
+            sys: SystemInfo {
+                boot_time: UNIX_EPOCH,
+                uptime: Duration::from_secs(8),
+                clk_tck: 100,
+                page_size: 4096,
+                mem_total: 1000,
+            },
+            now: UNIX_EPOCH + Duration::from_secs(8),
+            cpu: CpuMode::Lifetime,
+            forest: None,
+        }
+    }
+
+    #[test]
+    fn builds_records() {
+        let record = Record::new(&process(), &context());
+        assert_eq!(record.pid, 42);
+        assert_eq!(record.state, "S");
+        assert_eq!(record.cpu_percent, 25.0);
+        assert_eq!(record.mem_percent, 25.0);
+        assert_eq!(record.cpu_time, 2.0);
+        assert_eq!(record.tty.as_deref(), Some("pts/1"));
+        assert_eq!(record.cwd.as_deref(), Some("/tmp"));
+        assert_eq!(record.exe, None);
+        assert_eq!(record.unit.as_deref(), Some("cron.service"));
+    }
+
+    #[test]
+    fn writes_json() {
+        let mut out = vec![];
+        write_json(&mut out, &[process()], &context()).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value[0]["pid"], 42);
+        assert_eq!(value[0]["cmdline"][1], "a, \"b\"");
+        assert!(value[0]["exe"].is_null());
+    }
+
+    #[test]
+    fn writes_csv() {
+        let mut out = vec![];
+        write_csv(&mut out, &[process()], &context(), true).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let mut lines = text.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("pid,ppid,uid,user,name,state,cpu_percent"));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("42,1,0,root,sleep,S,25.0,25.0,250,1000,1,pts/1,"));
+        assert!(row.ends_with(
+            ",2.0,\"sleep a, \"\"b\"\"\",/tmp,,/system.slice/cron.service,cron.service,,"
+        ));
+
+        let mut out = vec![];
+        write_csv(&mut out, &[process()], &context(), false).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("42,"));
+    }
+}
@@ -0,0 +1,541 @@
+//! 输出列的定义和表格打印
+
+use crate::process::Process;
+use crate::procfs::SystemInfo;
+use crate::sample::CpuMode;
+use crate::tree;
+use chrono::{DateTime, Datelike, Local};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+/// ps 能输出的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Pid,
+    Ppid,
+    User,
+    Uid,
+    Cpu,
+    /// -f 中的 C：整数形式的 CPU 占用率
+    C,
+    Mem,
+    Vsz,
+    Rss,
+    Tty,
+    Stat,
+    /// BSD 的 START
+    Start,
+    /// POSIX 的 STIME
+    Stime,
+    /// [DD-]HH:MM:SS 形式的 CPU 时间
+    Time,
+    /// BSD 的 M:SS 形式的 CPU 时间
+    BsdTime,
+    Nlwp,
+    /// 线程号，进程本身的一行显示进程号
+    Lwp,
+    /// 最近一次运行所在的 CPU
+    Psr,
+    /// 进程名，-L、-T 中为线程名
+    Comm,
+    /// 完整命令行
+    Args,
+    /// cgroup 路径
+    Cgroup,
+    /// 所属的 systemd unit
+    Unit,
+}
+
+impl Column {
+    /// 按 -o 和 --sort 中使用的名字查找列，名字和 procps 相同
+    pub fn from_name(name: &str) -> Option<Column> {
+        Some(match name {
+            "pid" => Column::Pid,
+            "ppid" => Column::Ppid,
+            "user" | "euser" | "uname" => Column::User,
+            "uid" | "euid" => Column::Uid,
+            "%cpu" | "pcpu" => Column::Cpu,
+            "c" => Column::C,
+            "%mem" | "pmem" => Column::Mem,
+            "vsz" | "vsize" => Column::Vsz,
+            "rss" | "rssize" => Column::Rss,
+            "tty" | "tt" => Column::Tty,
+            "stat" => Column::Stat,
+            "start" => Column::Start,
+            "stime" | "start_time" => Column::Stime,
+            "time" | "cputime" => Column::Time,
+            "bsdtime" => Column::BsdTime,
+            "nlwp" | "thcount" => Column::Nlwp,
+            "lwp" | "tid" | "spid" => Column::Lwp,
+            "psr" => Column::Psr,
+            "comm" | "ucomm" => Column::Comm,
+            "args" | "command" | "cmd" => Column::Args,
+            "cgroup" => Column::Cgroup,
+            "unit" => Column::Unit,
+            _ => return None,
+        })
+    }
+
+    /// 默认表头
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Pid => "PID",
+            Column::Ppid => "PPID",
+            Column::User => "USER",
+            Column::Uid => "UID",
+            Column::Cpu => "%CPU",
+            Column::C => "C",
+            Column::Mem => "%MEM",
+            Column::Vsz => "VSZ",
+            Column::Rss => "RSS",
+            Column::Tty => "TTY",
+            Column::Stat => "STAT",
+            Column::Start => "START",
+            Column::Stime => "STIME",
+            Column::Time | Column::BsdTime => "TIME",
+            Column::Nlwp => "NLWP",
+            Column::Lwp => "LWP",
+            Column::Psr => "PSR",
+            Column::Comm | Column::Args => "COMMAND",
+            Column::Cgroup => "CGROUP",
+            Column::Unit => "UNIT",
+        }
+    }
+
+    /// 数字列右对齐，文本列左对齐
+    fn right_aligned(&self) -> bool {
+        !matches!(
+            self,
+            Column::User
+                | Column::Tty
+                | Column::Stat
+                | Column::Comm
+                | Column::Args
+                | Column::Cgroup
+                | Column::Unit
+        )
+    }
+
+    /// 按列的实际含义比较，数字列不按字符串比较
+    pub fn compare(&self, a: &Process, b: &Process, ctx: &Context) -> Ordering {
+        match self {
+            Column::Pid => a.pid.cmp(&b.pid),
+            Column::Ppid => a.ppid.cmp(&b.ppid),
+            Column::User => a.user.cmp(&b.user),
+            Column::Uid => a.uid.cmp(&b.uid),
+            Column::Cpu | Column::C => ctx.cpu_percent(a).total_cmp(&ctx.cpu_percent(b)),
+            Column::Mem | Column::Rss => a.rss.cmp(&b.rss),
+            Column::Vsz => a.vsz.cmp(&b.vsz),
+            Column::Tty => a.tty_nr.cmp(&b.tty_nr),
+            Column::Stat => a.stat().cmp(&b.stat()),
+            Column::Start | Column::Stime => a.start_time.cmp(&b.start_time),
+            Column::Time | Column::BsdTime => (a.utime + a.stime).cmp(&(b.utime + b.stime)),
+            Column::Nlwp => a.threads.cmp(&b.threads),
+            Column::Lwp => a.task_id().cmp(&b.task_id()),
+            Column::Psr => a.processor.cmp(&b.processor),
+            Column::Comm => a.name.cmp(&b.name),
+            Column::Args => a.command().cmp(&b.command()),
+            Column::Cgroup => a.cgroup.cmp(&b.cgroup),
+            Column::Unit => a.unit().cmp(&b.unit()),
+        }
+    }
+
+    pub fn value(&self, process: &Process, ctx: &Context) -> String {
+        let sys = &ctx.sys;
+        match self {
+            Column::Pid => process.pid.to_string(),
+            Column::Ppid => process.ppid.to_string(),
+            Column::User => process.user.clone(),
+            Column::Uid => process.uid.to_string(),
+            Column::Cpu => format!("{:.1}", ctx.cpu_percent(process)),
+            Column::C => format!("{}", ctx.cpu_percent(process).min(99.0) as u32),
+            Column::Mem => format!(
+                "{:.1}",
+                process.rss as f64 * 100.0 / sys.mem_total.max(1) as f64
+            ),
+            Column::Vsz => process.vsz.to_string(),
+            Column::Rss => process.rss.to_string(),
+            Column::Tty => process.tty().unwrap_or_else(|| "?".to_string()),
+            Column::Stat => process.stat(),
+            Column::Start | Column::Stime => format_start(process.start_time, ctx.now.into()),
+            Column::Time => format_time(process.cpu_time(sys.clk_tck)),
+            Column::BsdTime => format_bsd_time(process.cpu_time(sys.clk_tck)),
+            Column::Nlwp => process.threads.to_string(),
+            Column::Lwp => process.task_id().to_string(),
+            Column::Psr => process
+                .processor
+                .map_or_else(|| "-".to_string(), |cpu| cpu.to_string()),
+            Column::Comm => ctx.indent(process) + &process.name,
+            Column::Args => ctx.indent(process) + &process.command(),
+            Column::Cgroup => process.cgroup.clone().unwrap_or_else(|| "-".to_string()),
+            Column::Unit => process.unit().unwrap_or("-").to_string(),
+        }
+    }
+}
+
+/// 一个输出列及其表头
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub column: Column,
+    pub header: String,
+}
+
+impl Field {
+    pub fn new(column: Column) -> Field {
+        Field {
+            column,
+            header: column.header().to_string(),
+        }
+    }
+
+    pub fn with_header(column: Column, header: &str) -> Field {
+        Field {
+            column,
+            header: header.to_string(),
+        }
+    }
+}
+
+/// --sort 的一个排序键，例如 -%cpu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub column: Column,
+    pub descending: bool,
+}
+
+// --------------------------------------------------
+/// 按多个键排序，前面的键优先。排序是稳定的，相同的进程保持进程号顺序
+pub fn sort(processes: &mut [Process], keys: &[SortKey], ctx: &Context) {
+    processes.sort_by(|a, b| {
+        keys.iter()
+            .map(|key| {
+                let ordering = key.column.compare(a, b, ctx);
+                if key.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+/// 标准的列组合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// ps：PID TTY TIME CMD
+    Default,
+    /// ps -f：UID PID PPID C STIME TTY TIME CMD
+    Full,
+    /// ps ax：PID TTY STAT TIME COMMAND
+    Bsd,
+    /// ps aux：USER PID %CPU %MEM VSZ RSS TTY STAT START TIME COMMAND
+    BsdUser,
+}
+
+impl Layout {
+    pub fn fields(&self) -> Vec<Field> {
+        match self {
+            Layout::Default => vec![
+                Field::new(Column::Pid),
+                Field::new(Column::Tty),
+                Field::new(Column::Time),
+                Field::with_header(Column::Comm, "CMD"),
+            ],
+            Layout::Full => vec![
+                Field::with_header(Column::User, "UID"),
+                Field::new(Column::Pid),
+                Field::new(Column::Ppid),
+                Field::new(Column::C),
+                Field::new(Column::Stime),
+                Field::new(Column::Tty),
+                Field::new(Column::Time),
+                Field::with_header(Column::Args, "CMD"),
+            ],
+            Layout::Bsd => vec![
+                Field::new(Column::Pid),
+                Field::new(Column::Tty),
+                Field::new(Column::Stat),
+                Field::new(Column::BsdTime),
+                Field::new(Column::Args),
+            ],
+            Layout::BsdUser => vec![
+                Field::new(Column::User),
+                Field::new(Column::Pid),
+                Field::new(Column::Cpu),
+                Field::new(Column::Mem),
+                Field::new(Column::Vsz),
+                Field::new(Column::Rss),
+                Field::new(Column::Tty),
+                Field::new(Column::Stat),
+                Field::new(Column::Start),
+                Field::new(Column::BsdTime),
+                Field::new(Column::Args),
+            ],
+        }
+    }
+
+    /// -L、-T 的标准列，和 procps 相同：加上线程号列 lwp（表头为 LWP 或
+    /// SPID），-f 中放在 PPID 后面并加上 NLWP，其余格式放在 PID 后面
+    pub fn thread_fields(&self, lwp: &Field) -> Vec<Field> {
+        let mut fields = self.fields();
+        let at = |fields: &[Field], column| fields.iter().position(|f| f.column == column);
+        let after = if *self == Layout::Full {
+            Column::Ppid
+        } else {
+            Column::Pid
+        };
+        let i = at(&fields, after).map_or(0, |i| i + 1);
+        fields.insert(i, lwp.clone());
+        if *self == Layout::Full {
+            let c = at(&fields, Column::C).map_or(fields.len(), |i| i + 1);
+            fields.insert(c, Field::new(Column::Nlwp));
+        }
+        fields
+    }
+}
+
+/// 计算列值时需要的上下文
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub sys: SystemInfo,
+    pub now: SystemTime,
+    pub cpu: CpuMode,
+    /// --forest 时每个进程在树中的深度
+    pub forest: Option<HashMap<i32, usize>>,
+}
+
+impl Context {
+    /// 按选定的方式计算 %CPU，没有采样结果的进程按 0 计算
+    pub fn cpu_percent(&self, process: &Process) -> f64 {
+        match &self.cpu {
+            CpuMode::Lifetime => process.cpu_percent(self.sys.clk_tck, self.now),
+            CpuMode::Sampled { percent, .. } => {
+                percent.get(&process.task_id()).copied().unwrap_or(0.0)
+            }
+        }
+    }
+
+    /// --forest 时命令前的缩进
+    fn indent(&self, process: &Process) -> String {
+        match &self.forest {
+            Some(depth) => tree::forest_prefix(depth.get(&process.pid).copied().unwrap_or(0)),
+            None => String::new(),
+        }
+    }
+
+    /// 采样模式下在 %CPU 和 C 的默认表头后注明采样间隔，自定义的表头不变
+    pub fn label_headers(&self, fields: &mut [Field]) {
+        let Some(label) = self.cpu.label() else {
+            return;
+        };
+        for field in fields {
+            if matches!(field.column, Column::Cpu | Column::C)
+                && field.header == field.column.header()
+            {
+                field.header = format!("{}({})", field.header, label);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------
+/// 打印表头和每个进程的一行
+pub fn print_table(
+    out: &mut impl Write,
+    fields: &[Field],
+    processes: &[Process],
+    ctx: &Context,
+    headers: bool,
+) -> io::Result<()> {
+    for line in format_table(fields, processes, ctx, headers) {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()
+}
+
+// --------------------------------------------------
+/// 排好版的表格，每行一个字符串。列宽取表头和所有值中最长的一个；所有
+/// 表头都为空（例如 -o pid=）或 headers 为 false 时没有表头行
+pub fn format_table(
+    fields: &[Field],
+    processes: &[Process],
+    ctx: &Context,
+    headers: bool,
+) -> Vec<String> {
+    let rows: Vec<Vec<String>> = processes
+        .iter()
+        .map(|process| {
+            fields
+                .iter()
+                .map(|field| field.column.value(process, ctx))
+                .collect()
+        })
+        .collect();
+    let header: Vec<String> = fields.iter().map(|field| field.header.clone()).collect();
+    let headers = headers && header.iter().any(|h| !h.is_empty());
+
+    let mut widths: Vec<usize> = if headers {
+        header.iter().map(|h| h.chars().count()).collect()
+    } else {
+        vec![0; fields.len()]
+    };
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let mut lines = Vec::with_capacity(rows.len() + 1);
+    if headers {
+        lines.push(format_row(fields, &widths, &header));
+    }
+    for row in &rows {
+        lines.push(format_row(fields, &widths, row));
+    }
+    lines
+}
+
+// --------------------------------------------------
+fn format_row(fields: &[Field], widths: &[usize], values: &[String]) -> String {
+    let mut line = String::new();
+    for (i, (field, value)) in fields.iter().zip(values).enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        let pad = " ".repeat(widths[i].saturating_sub(value.chars().count()));
+        if field.column.right_aligned() {
+            line.push_str(&pad);
+            line.push_str(value);
+        } else {
+            line.push_str(value);
+            // 最后一列左对齐时不补空格，避免行尾空白
+            if i + 1 < fields.len() {
+                line.push_str(&pad);
+            }
+        }
+    }
+    line
+}
+
+// --------------------------------------------------
+/// 和 procps 一样：当天启动的显示时间，当年的显示月日，否则显示年份
+pub fn format_start(start: SystemTime, now: DateTime<Local>) -> String {
+    let start: DateTime<Local> = start.into();
+    if start.date_naive() == now.date_naive() {
+        start.format("%H:%M").to_string()
+    } else if start.year() == now.year() {
+        start.format("%b%d").to_string()
+    } else {
+        start.format("%Y").to_string()
+    }
+}
+
+// --------------------------------------------------
+/// [DD-]HH:MM:SS 形式的 CPU 时间
+pub fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let hms = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}-{}", days, hms)
+    } else {
+        hms
+    }
+}
+
+// --------------------------------------------------
+/// BSD 的 分:秒 形式的 CPU 时间
+pub fn format_bsd_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn process(pid: i32, name: &str, rss: u64) -> Process {
+        Process {
+            vsz: rss * 2,
+            rss,
+            ..Process::fixture(pid, name)
+        }
+    }
+
+    fn context() -> Context {
+        Context {
+            sys: SystemInfo {
+                boot_time: UNIX_EPOCH,
+                uptime: Duration::from_secs(100),
+                clk_tck: 100,
+                page_size: 4096,
+                mem_total: 1000,
+            },
+            now: UNIX_EPOCH + Duration::from_secs(100),
+            cpu: CpuMode::Lifetime,
+            forest: None,
+        }
+    }
+
+    fn table(fields: &[Field], processes: &[Process], headers: bool) -> String {
+        let mut out = vec![];
+        print_table(&mut out, fields, processes, &context(), headers).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn sizes_columns_to_content() {
+        let fields = [
+            Field::with_header(Column::Pid, "ID"),
+            Field::new(Column::Rss),
+            Field::new(Column::Comm),
+        ];
+        let processes = [process(7, "init", 12345), process(12345, "sh", 5)];
+        assert_eq!(
+            table(&fields, &processes, true),
+            "   ID   RSS COMMAND\n    7 12345 init\n12345     5 sh\n"
+        );
+        assert_eq!(
+            table(&fields, &processes, false),
+            "    7 12345 init\n12345     5 sh\n"
+        );
+        // 表头全部为空时不打印表头
+        let fields = [Field::with_header(Column::Pid, "")];
+        assert_eq!(table(&fields, &processes, true), "    7\n12345\n");
+    }
+
+    #[test]
+    fn sorts_by_several_keys() {
+        let mut processes = vec![
+            process(1, "b", 10),
+            process(2, "a", 30),
+            process(3, "b", 30),
+        ];
+        let keys = [
+            SortKey {
+                column: Column::Rss,
+                descending: true,
+            },
+            SortKey {
+                column: Column::Comm,
+                descending: false,
+            },
+        ];
+        sort(&mut processes, &keys, &context());
+        let pids: Vec<i32> = processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn formats_cpu_time() {
+        assert_eq!(format_time(Duration::from_secs(0)), "00:00:00");
+        assert_eq!(format_time(Duration::from_secs(3725)), "01:02:05");
+        assert_eq!(format_time(Duration::from_secs(90061)), "1-01:01:01");
+        assert_eq!(format_bsd_time(Duration::from_secs(3725)), "62:05");
+    }
+}
@@ -0,0 +1,240 @@
+//! --group cgroup|unit：按 cgroup 或 systemd unit 汇总 CPU 和内存
+
+use crate::export::OutputFormat;
+use crate::format::Context;
+use crate::process::Process;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// 汇总的依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Cgroup,
+    Unit,
+}
+
+impl GroupBy {
+    pub fn from_name(name: &str) -> Option<GroupBy> {
+        match name {
+            "cgroup" => Some(GroupBy::Cgroup),
+            "unit" => Some(GroupBy::Unit),
+            _ => None,
+        }
+    }
+
+    fn header(&self) -> &'static str {
+        match self {
+            GroupBy::Cgroup => "CGROUP",
+            GroupBy::Unit => "UNIT",
+        }
+    }
+
+    /// 进程所在的组，没有 cgroup 或 unit 的进程归入 "-"
+    fn key<'a>(&self, process: &'a Process) -> &'a str {
+        match self {
+            GroupBy::Cgroup => process.cgroup.as_deref(),
+            GroupBy::Unit => process.unit(),
+        }
+        .unwrap_or("-")
+    }
+}
+
+/// 一个 cgroup 或 unit 中所有进程的合计
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub processes: usize,
+    pub cpu_percent: f64,
+    pub mem_percent: f64,
+    pub rss_kb: u64,
+}
+
+// --------------------------------------------------
+/// 汇总各组，按 %CPU 从高到低排列，相同时按常驻内存
+pub fn group(processes: &[Process], by: GroupBy, ctx: &Context) -> Vec<Group> {
+    let mut groups: HashMap<&str, Group> = HashMap::new();
+    for process in processes {
+        let name = by.key(process);
+        let group = groups.entry(name).or_insert_with(|| Group {
+            name: name.to_string(),
+            processes: 0,
+            cpu_percent: 0.0,
+            mem_percent: 0.0,
+            rss_kb: 0,
+        });
+        group.processes += 1;
+        group.cpu_percent += ctx.cpu_percent(process);
+        group.rss_kb += process.rss;
+    }
+    let mut groups: Vec<Group> = groups
+        .into_values()
+        .map(|group| Group {
+            cpu_percent: round(group.cpu_percent),
+            mem_percent: round(group.rss_kb as f64 * 100.0 / ctx.sys.mem_total.max(1) as f64),
+            ..group
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.cpu_percent
+            .total_cmp(&a.cpu_percent)
+            .then(b.rss_kb.cmp(&a.rss_kb))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    groups
+}
+
+// --------------------------------------------------
+fn round(percent: f64) -> f64 {
+    (percent * 10.0).round() / 10.0
+}
+
+// --------------------------------------------------
+/// 按选定的格式输出汇总结果。表格中名字放在最后一列，路径再长也不影响对齐
+pub fn print_groups(
+    out: &mut impl Write,
+    groups: &[Group],
+    by: GroupBy,
+    output: OutputFormat,
+    headers: bool,
+) -> io::Result<()> {
+    match output {
+        OutputFormat::Table => {
+            for line in format_groups(groups, by, headers) {
+                writeln!(out, "{}", line)?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, groups)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(headers)
+                .from_writer(&mut *out);
+            for group in groups {
+                writer.serialize(group)?;
+            }
+            writer.flush()?;
+        }
+    }
+    out.flush()
+}
+
+// --------------------------------------------------
+fn format_groups(groups: &[Group], by: GroupBy, headers: bool) -> Vec<String> {
+    let mut rows: Vec<[String; 5]> = vec![];
+    if headers {
+        rows.push(["NPROC", "%CPU", "%MEM", "RSS", by.header()].map(str::to_string));
+    }
+    for group in groups {
+        rows.push([
+            group.processes.to_string(),
+            format!("{:.1}", group.cpu_percent),
+            format!("{:.1}", group.mem_percent),
+            group.rss_kb.to_string(),
+            group.name.clone(),
+        ]);
+    }
+    let widths: Vec<usize> = (0..4)
+        .map(|i| rows.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            format!(
+                "{:>w0$} {:>w1$} {:>w2$} {:>w3$} {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                row[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::SystemInfo;
+    use crate::sample::CpuMode;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn process(pid: i32, cgroup: Option<&str>, ticks: u64, rss: u64) -> Process {
+        Process {
+            utime: ticks,
+            rss,
+            cgroup: cgroup.map(str::to_string),
+            ..Process::fixture(pid, "worker")
+        }
+    }
+
+    fn context() -> Context {
+        Context {
+            sys: SystemInfo {
+                boot_time: UNIX_EPOCH,
+                uptime: Duration::from_secs(10),
+                clk_tck: 100,
+                page_size: 4096,
+                mem_total: 1000,
+            },
+            now: UNIX_EPOCH + Duration::from_secs(10),
+            cpu: CpuMode::Lifetime,
+            forest: None,
+        }
+    }
+
+    fn processes() -> Vec<Process> {
+        vec![
+            process(1, Some("/system.slice/sshd.service"), 100, 100),
+            process(2, Some("/system.slice/sshd.service/sub"), 50, 50),
+            process(3, Some("/system.slice/docker-ab12.scope"), 500, 10),
+            process(4, None, 0, 5),
+        ]
+    }
+
+    #[test]
+    fn sums_per_unit() {
+        let groups = group(&processes(), GroupBy::Unit, &context());
+        let names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["docker-ab12.scope", "sshd.service", "-"]);
+        assert_eq!(
+            groups[1],
+            Group {
+                name: "sshd.service".to_string(),
+                processes: 2,
+                cpu_percent: 15.0,
+                mem_percent: 15.0,
+                rss_kb: 150,
+            }
+        );
+    }
+
+    #[test]
+    fn sums_per_cgroup() {
+        let groups = group(&processes(), GroupBy::Cgroup, &context());
+        assert_eq!(groups.len(), 4);
+        assert_eq!(groups[0].name, "/system.slice/docker-ab12.scope");
+        assert_eq!(groups[0].cpu_percent, 50.0);
+    }
+
+    #[test]
+    fn formats_groups() {
+        let groups = group(&processes(), GroupBy::Unit, &context());
+        let lines = format_groups(&groups, GroupBy::Unit, true);
+        assert_eq!(
+            lines,
+            vec![
+                "NPROC %CPU %MEM RSS UNIT",
+                "    1 50.0  1.0  10 docker-ab12.scope",
+                "    2 15.0 15.0 150 sshd.service",
+                "    1  0.0  0.5   5 -",
+            ]
+        );
+        assert_eq!(format_groups(&groups, GroupBy::Unit, false).len(), 3);
+    }
+}
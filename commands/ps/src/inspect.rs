@@ -0,0 +1,499 @@
+//! --inspect PID：单个进程的详细报告
+//!
+//! 包括打开的文件（类似 lsof，socket 解析成地址）、内存映射汇总、环境
+//! 变量、资源限制、cgroup、命名空间和 I/O 计数。大部分文件只有进程的属主
+//! 或 root 才能读，读不到的部分在报告中注明原因，不影响其他部分。
+
+use crate::process::Process;
+use crate::procfs::{self, Cgroup};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+/// 最大的文件映射显示多少个
+const TOP_FILES: usize = 10;
+
+/// 一个打开的文件描述符
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fd {
+    pub fd: u32,
+    /// file、dir、chr、pipe、socket、anon 等
+    pub kind: &'static str,
+    /// 链接目标，socket 解析成协议和地址
+    pub target: String,
+}
+
+// --------------------------------------------------
+/// 根据 /proc/[pid]/fd/N 的链接目标判断类型
+pub fn fd_kind(target: &str) -> &'static str {
+    if target.starts_with("socket:[") {
+        "socket"
+    } else if target.starts_with("pipe:[") {
+        "pipe"
+    } else if target.starts_with("anon_inode:") {
+        "anon"
+    } else if target.starts_with("/dev/") {
+        "dev"
+    } else if target.starts_with('/') {
+        "file"
+    } else {
+        "other"
+    }
+}
+
+// --------------------------------------------------
+/// 读取进程打开的文件。sockets 是 inode 到地址描述的映射
+fn read_fds(dir: &Path, sockets: &HashMap<u64, String>) -> io::Result<Vec<Fd>> {
+    let mut fds = vec![];
+    for entry in fs::read_dir(dir.join("fd"))? {
+        let entry = entry?;
+        let Some(fd) = entry.file_name().to_str().and_then(|n| n.parse().ok()) else {
+            continue;
+        };
+        // 列目录和读链接之间文件可能已经关闭
+        let Ok(target) = fs::read_link(entry.path()) else {
+            continue;
+        };
+        let target = target.to_string_lossy().into_owned();
+        let kind = fd_kind(&target);
+        let target = socket_inode(&target)
+            .and_then(|inode| sockets.get(&inode).cloned())
+            .unwrap_or(target);
+        fds.push(Fd { fd, kind, target });
+    }
+    fds.sort_by_key(|fd| fd.fd);
+    Ok(fds)
+}
+
+// --------------------------------------------------
+fn socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+// --------------------------------------------------
+/// 读取进程所在网络命名空间的 tcp、udp 和 unix socket
+fn read_sockets(dir: &Path) -> HashMap<u64, String> {
+    let mut sockets = HashMap::new();
+    for proto in ["tcp", "tcp6", "udp", "udp6"] {
+        if let Ok(text) = fs::read_to_string(dir.join("net").join(proto)) {
+            sockets.extend(parse_inet_sockets(&text, proto));
+        }
+    }
+    if let Ok(text) = fs::read_to_string(dir.join("net").join("unix")) {
+        sockets.extend(parse_unix_sockets(&text));
+    }
+    sockets
+}
+
+// --------------------------------------------------
+/// 解析 /proc/net/tcp 等文件，返回 inode 到 "TCP 本地 -> 远端 (状态)" 的映射
+pub fn parse_inet_sockets(text: &str, proto: &str) -> HashMap<u64, String> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local = parse_address(fields.get(1)?)?;
+            let remote = parse_address(fields.get(2)?)?;
+            let state = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+            let inode = fields.get(9)?.parse().ok()?;
+            let name = proto.trim_end_matches('6').to_uppercase();
+            let description = if proto.starts_with("tcp") {
+                format!("{} {} -> {} ({})", name, local, remote, tcp_state(state))
+            } else {
+                format!("{} {} -> {}", name, local, remote)
+            };
+            Some((inode, description))
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+/// 0100007F:1F90 这样的地址。内核按主机字节序输出每个 32 位字
+fn parse_address(text: &str) -> Option<String> {
+    let (ip, port) = text.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let words: Vec<u32> = (0..ip.len() / 8)
+        .map(|i| u32::from_str_radix(&ip[i * 8..i * 8 + 8], 16).map(u32::from_be))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match words.as_slice() {
+        [ip] => Some(format!("{}:{}", Ipv4Addr::from(ip.to_be_bytes()), port)),
+        [a, b, c, d] => {
+            let mut bytes = [0u8; 16];
+            for (i, word) in [a, b, c, d].iter().enumerate() {
+                bytes[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+            }
+            Some(format!("[{}]:{}", Ipv6Addr::from(bytes), port))
+        }
+        _ => None,
+    }
+}
+
+// --------------------------------------------------
+fn tcp_state(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// --------------------------------------------------
+/// 解析 /proc/net/unix，没有路径的 socket 显示为匿名
+pub fn parse_unix_sockets(text: &str) -> HashMap<u64, String> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let inode = fields.get(6)?.parse().ok()?;
+            let path = fields.get(7).copied().unwrap_or("(匿名)");
+            Some((inode, format!("UNIX {}", path)))
+        })
+        .collect()
+}
+
+/// 内存映射的汇总，单位 KiB
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapSummary {
+    pub regions: usize,
+    pub total: u64,
+    pub anonymous: u64,
+    pub heap: u64,
+    pub stack: u64,
+    pub file: u64,
+    /// 按映射大小排列的文件
+    pub files: Vec<(String, u64)>,
+}
+
+// --------------------------------------------------
+/// 解析 /proc/[pid]/maps 并按类型汇总
+pub fn parse_maps(text: &str) -> MapSummary {
+    let mut summary = MapSummary::default();
+    let mut files: HashMap<String, u64> = HashMap::new();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let Some((start, end)) = fields.next().and_then(|range| range.split_once('-')) else {
+            continue;
+        };
+        let (Ok(start), Ok(end)) = (u64::from_str_radix(start, 16), u64::from_str_radix(end, 16))
+        else {
+            continue;
+        };
+        let size = end.saturating_sub(start) / 1024;
+        // 路径可能包含空格，取第 6 个字段之后的全部内容
+        let path = fields.nth(4).map(|_| {
+            line.splitn(6, char::is_whitespace)
+                .nth(5)
+                .unwrap_or("")
+                .trim_start()
+        });
+        summary.regions += 1;
+        summary.total += size;
+        match path.unwrap_or("") {
+            "" => summary.anonymous += size,
+            "[heap]" => summary.heap += size,
+            path if path.starts_with("[stack") => summary.stack += size,
+            path if path.starts_with('[') => summary.anonymous += size,
+            path => {
+                summary.file += size;
+                *files.entry(path.to_string()).or_default() += size;
+            }
+        }
+    }
+    summary.files = files.into_iter().collect();
+    summary
+        .files
+        .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    summary
+}
+
+/// /proc/[pid]/limits 中的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limit {
+    pub name: String,
+    pub soft: String,
+    pub hard: String,
+    pub units: String,
+}
+
+// --------------------------------------------------
+/// 解析 /proc/[pid]/limits。各列按表头中的位置对齐，名字中有空格，所以
+/// 按表头的列位置切分
+pub fn parse_limits(text: &str) -> Vec<Limit> {
+    let mut lines = text.lines();
+    let Some(header) = lines.next() else {
+        return vec![];
+    };
+    let (Some(soft), Some(hard), Some(units)) = (
+        header.find("Soft Limit"),
+        header.find("Hard Limit"),
+        header.find("Units"),
+    ) else {
+        return vec![];
+    };
+    let column = |line: &str, from: usize, to: usize| {
+        line.get(from..to.min(line.len()))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    lines
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Limit {
+            name: column(line, 0, soft),
+            soft: column(line, soft, hard),
+            hard: column(line, hard, units),
+            units: column(line, units, line.len()),
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+/// 解析 /proc/[pid]/io 的 "key: value" 行
+pub fn parse_io(text: &str) -> Vec<(String, u64)> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+/// 命名空间，例如 ("net", "net:[4026531840]")
+fn read_namespaces(dir: &Path) -> io::Result<Vec<(String, String)>> {
+    let mut namespaces = vec![];
+    for entry in fs::read_dir(dir.join("ns"))? {
+        let entry = entry?;
+        let target = fs::read_link(entry.path())?;
+        namespaces.push((
+            entry.file_name().to_string_lossy().into_owned(),
+            target.to_string_lossy().into_owned(),
+        ));
+    }
+    namespaces.sort();
+    Ok(namespaces)
+}
+
+// --------------------------------------------------
+/// 打印完整报告
+pub fn report(out: &mut impl Write, root: &Path, process: &Process) -> io::Result<()> {
+    let dir = root.join(process.pid.to_string());
+    let show_path = |path: &Option<std::path::PathBuf>| match path {
+        Some(path) => path.display().to_string(),
+        None => "?".to_string(),
+    };
+
+    writeln!(out, "进程 {} ({})", process.pid, process.name)?;
+    writeln!(out, "  状态        {}", process.stat())?;
+    writeln!(out, "  用户        {} ({})", process.user, process.uid)?;
+    writeln!(out, "  父进程      {}", process.ppid)?;
+    writeln!(out, "  线程数      {}", process.threads)?;
+    writeln!(out, "  命令行      {}", process.command())?;
+    writeln!(out, "  当前目录    {}", show_path(&process.cwd))?;
+    writeln!(out, "  可执行文件  {}", show_path(&process.exe))?;
+
+    let sockets = read_sockets(&dir);
+    section(
+        out,
+        "打开的文件",
+        read_fds(&dir, &sockets),
+        |out, fds| {
+            writeln!(out, "  共 {} 个", fds.len())?;
+            for fd in fds {
+                writeln!(out, "  {:>5} {:<6} {}", fd.fd, fd.kind, fd.target)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    let maps = fs::read_to_string(dir.join("maps")).map(|text| parse_maps(&text));
+    section(out, "内存映射", maps, |out, maps| {
+        writeln!(out, "  区域数      {}", maps.regions)?;
+        writeln!(out, "  总计        {} KiB", maps.total)?;
+        writeln!(out, "  文件        {} KiB", maps.file)?;
+        writeln!(out, "  匿名        {} KiB", maps.anonymous)?;
+        writeln!(out, "  堆          {} KiB", maps.heap)?;
+        writeln!(out, "  栈          {} KiB", maps.stack)?;
+        if !maps.files.is_empty() {
+            writeln!(out, "  映射最多的文件:")?;
+            for (path, size) in maps.files.iter().take(TOP_FILES) {
+                writeln!(out, "  {:>10} KiB  {}", size, path)?;
+            }
+        }
+        Ok(())
+    })?;
+
+    // 环境变量在读取进程时已经读过，读不到时为空
+    writeln!(out)?;
+    writeln!(out, "环境变量")?;
+    if process.environ.is_empty() {
+        writeln!(out, "  （空或无权限读取）")?;
+    }
+    for var in &process.environ {
+        writeln!(out, "  {}", var)?;
+    }
+
+    let limits = fs::read_to_string(dir.join("limits")).map(|text| parse_limits(&text));
+    section(out, "资源限制", limits, |out, limits| {
+        // 中文表头每个字占两列，按显示宽度对齐
+        writeln!(
+            out,
+            "  {:<24}{:>12}{:>12}  单位",
+            "限制", "软限制", "硬限制"
+        )?;
+        for limit in limits {
+            let line = format!(
+                "  {:<26}{:>15}{:>15}  {}",
+                limit.name, limit.soft, limit.hard, limit.units
+            );
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    })?;
+
+    let cgroups = fs::read_to_string(dir.join("cgroup")).map(|text| procfs::parse_cgroup(&text));
+    section(out, "cgroup", cgroups, |out, cgroups: &Vec<Cgroup>| {
+        for cgroup in cgroups {
+            let controllers = if cgroup.controllers.is_empty() {
+                "(v2)".to_string()
+            } else {
+                cgroup.controllers.join(",")
+            };
+            writeln!(out, "  {:<20} {}", controllers, cgroup.path)?;
+        }
+        Ok(())
+    })?;
+
+    section(
+        out,
+        "命名空间",
+        read_namespaces(&dir),
+        |out, namespaces| {
+            for (name, target) in namespaces {
+                writeln!(out, "  {:<18} {}", name, target)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    let io_counters = fs::read_to_string(dir.join("io")).map(|text| parse_io(&text));
+    section(out, "I/O", io_counters, |out, counters| {
+        for (key, value) in counters {
+            writeln!(out, "  {:<22} {}", key, value)?;
+        }
+        Ok(())
+    })?;
+    out.flush()
+}
+
+// --------------------------------------------------
+/// 打印一节，读取失败时打印原因
+fn section<T>(
+    out: &mut impl Write,
+    title: &str,
+    data: io::Result<T>,
+    body: impl FnOnce(&mut dyn Write, &T) -> io::Result<()>,
+) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", title)?;
+    match data {
+        Ok(data) => body(out, &data),
+        Err(e) => writeln!(out, "  （无法读取: {}）", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_fds() {
+        assert_eq!(fd_kind("/dev/pts/0"), "dev");
+        assert_eq!(fd_kind("/home/a.txt (deleted)"), "file");
+        assert_eq!(fd_kind("socket:[1234]"), "socket");
+        assert_eq!(fd_kind("pipe:[99]"), "pipe");
+        assert_eq!(fd_kind("anon_inode:[eventpoll]"), "anon");
+        assert_eq!(socket_inode("socket:[1234]"), Some(1234));
+    }
+
+    #[test]
+    fn parses_sockets() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+                   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 662 1 0 100 0 0 10 0\n";
+        let sockets = parse_inet_sockets(tcp, "tcp");
+        assert_eq!(sockets[&662], "TCP 127.0.0.1:8080 -> 0.0.0.0:0 (LISTEN)");
+
+        let tcp6 = "  sl  local_address remote_address st\n\
+                    0: 00000000000000000000000001000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000 0 0 777 1\n";
+        let sockets = parse_inet_sockets(tcp6, "tcp6");
+        assert_eq!(sockets[&777], "TCP [::1]:22 -> [::]:0 (LISTEN)");
+
+        let unix = "Num       RefCount Protocol Flags    Type St Inode Path\n\
+                    0000000042b82dba: 00000002 00000000 00010000 0001 01 27506 /tmp/a.sock\n\
+                    000000003fdc6baf: 00000003 00000000 00000000 0001 03  1035\n";
+        let sockets = parse_unix_sockets(unix);
+        assert_eq!(sockets[&27506], "UNIX /tmp/a.sock");
+        assert_eq!(sockets[&1035], "UNIX (匿名)");
+    }
+
+    #[test]
+    fn summarizes_maps() {
+        let maps = "00400000-00402000 r-xp 00000000 fe:00 1 /usr/bin/my app\n\
+                    00402000-00403000 r--p 00002000 fe:00 1 /usr/bin/my app\n\
+                    01000000-01004000 rw-p 00000000 00:00 0 [heap]\n\
+                    7f0000000000-7f0000010000 rw-p 00000000 00:00 0\n\
+                    7f0000010000-7f0000011000 r-xp 00000000 fe:00 2 /lib/libc.so\n\
+                    7ffc00000000-7ffc00021000 rw-p 00000000 00:00 0 [stack]\n\
+                    7ffc00030000-7ffc00032000 r-xp 00000000 00:00 0 [vdso]\n";
+        let summary = parse_maps(maps);
+        assert_eq!(summary.regions, 7);
+        assert_eq!(summary.file, 12 + 4);
+        assert_eq!(summary.heap, 16);
+        assert_eq!(summary.stack, 132);
+        assert_eq!(summary.anonymous, 64 + 8);
+        assert_eq!(summary.total, 12 + 4 + 16 + 132 + 72);
+        assert_eq!(
+            summary.files,
+            vec![
+                ("/usr/bin/my app".to_string(), 12),
+                ("/lib/libc.so".to_string(), 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_limits_and_io() {
+        let limits = "Limit                     Soft Limit           Hard Limit           Units     \n\
+                      Max cpu time              unlimited            unlimited            seconds   \n\
+                      Max open files            1024                 524288               files     \n";
+        let limits = parse_limits(limits);
+        assert_eq!(
+            limits[1],
+            Limit {
+                name: "Max open files".to_string(),
+                soft: "1024".to_string(),
+                hard: "524288".to_string(),
+                units: "files".to_string(),
+            }
+        );
+        let io = parse_io("rchar: 3980\nwchar: 0\nread_bytes: 4096\n");
+        assert_eq!(io[0], ("rchar".to_string(), 3980));
+        assert_eq!(io.len(), 3);
+    }
+}
@@ -0,0 +1,232 @@
+//! # 使用Rust编写的ps命令
+//!
+//! 直接解析 Linux 的 /proc 文件系统：
+//! - /proc/[pid]/stat: 进程名、状态、父进程、终端、CPU 时间、启动时间、线程数
+//! - /proc/[pid]/status: 用户和组
+//! - /proc/[pid]/statm: 虚拟内存和常驻内存
+//! - /proc/[pid]/cmdline、/proc/[pid]/environ: 命令行和环境变量
+//! - /proc/[pid]/cwd、/proc/[pid]/exe: 当前目录和可执行文件
+//! - /proc/[pid]/cgroup: cgroup 路径和 systemd unit
+//! - /proc/[pid]/task/[tid]/stat: -L、-T 中每个线程的名字、状态、CPU 时间和所在的 CPU
+//!
+//! --inspect 另外读取 fd、maps、limits、cgroup、ns、io 和 net/*，见 [`inspect`]。
+//!
+//! 解析结果是带类型的 [`Process`]，输出格式化只依赖这个结构体。读取方式
+//! 由 [`ProcessSource`] 抽象。ps 只支持 Linux，`sysinfo` feature 提供的
+//! 后端也不例外，它缺少 CPU 时间等字段，不能替代 /proc。
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub mod args;
+pub mod error;
+pub mod export;
+pub mod format;
+pub mod group;
+pub mod inspect;
+pub mod matcher;
+pub mod process;
+pub mod procfs;
+pub mod record;
+pub mod sample;
+pub mod select;
+pub mod signal;
+pub mod source;
+pub mod top;
+pub mod tree;
+pub mod users;
+
+pub use error::PsError;
+pub use export::{OutputFormat, Record};
+pub use format::{Column, Context, Field, Layout, SortKey};
+pub use group::GroupBy;
+pub use matcher::Matcher;
+pub use process::{Process, State};
+pub use procfs::SystemInfo;
+pub use sample::CpuMode;
+pub use select::{Caller, Selection};
+pub use source::{default_source, MockSource, ProcFs, ProcessSource};
+pub use tree::PstreeOptions;
+pub use users::Users;
+
+pub const PROC: &str = "/proc";
+pub const PASSWD: &str = "/etc/passwd";
+
+/// 主流程：从当前平台的默认来源读取进程，输出到标准输出
+pub fn run(args: &[String]) -> Result<(), PsError> {
+    let users = Users::read(Path::new(PASSWD));
+    let stdout = io::stdout();
+    run_with(&*default_source(), &users, args, &mut stdout.lock())
+}
+
+// --------------------------------------------------
+/// 解析参数，从 source 读取并筛选进程，按选定的列输出到 out
+pub fn run_with(
+    source: &dyn ProcessSource,
+    users: &Users,
+    args: &[String],
+    out: &mut impl Write,
+) -> Result<(), PsError> {
+    let options = args::parse(args, users)?;
+    if options.help {
+        writeln!(out, "{}", args::USAGE)?;
+        return Ok(());
+    }
+
+    if let Some(path) = &options.replay {
+        let mut reader = record::Reader::open(path)?;
+        if !options.top {
+            record::print_peaks(out, &mut reader, options.output, options.headers)?;
+            return Ok(());
+        }
+        let interval = options.interval.unwrap_or(reader.header.interval());
+        let replay = record::Replay::new(reader)?;
+        let fields = options.fields.unwrap_or_else(top::default_fields);
+        return top::run(&replay, replay.system()?, users, fields, interval);
+    }
+    if let Some(path) = &options.record {
+        let interval = options.interval.unwrap_or(Duration::from_secs(1));
+        return record::record(out, path, source, users, interval);
+    }
+    let sys = source.system()?;
+    if options.top {
+        let fields = options.fields.unwrap_or_else(top::default_fields);
+        let interval = options.sample.unwrap_or(Duration::from_secs(1));
+        return top::run(source, sys, users, fields, interval);
+    }
+    if let Some(pid) = options.inspect {
+        let root = source
+            .proc_root()
+            .ok_or_else(|| PsError::Usage("--inspect 需要 /proc".to_string()))?;
+        let process = match source.process(pid, &sys, users) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let msg = format!("进程 {} 不存在", pid);
+                return Err(io::Error::new(io::ErrorKind::NotFound, msg).into());
+            }
+            result => result?,
+        };
+        inspect::report(out, root, &process)?;
+        return Ok(());
+    }
+    let caller = caller(source, &sys, users)?;
+    let threads = options.threads.is_some();
+    let (processes, cpu) = match options.sample {
+        None => (source.processes(&sys, users)?, CpuMode::Lifetime),
+        Some(interval) => {
+            // -L、-T 按线程采样，每个线程显示自己的占用率
+            let sampled = |processes: &[Process]| -> io::Result<Vec<Process>> {
+                if threads {
+                    tasks(source, processes, &sys)
+                } else {
+                    Ok(processes.to_vec())
+                }
+            };
+            let before = sampled(&source.processes(&sys, users)?)?;
+            let started = Instant::now();
+            thread::sleep(interval);
+            let after = source.processes(&sys, users)?;
+            // 用实际经过的时间，读取 /proc 本身也要花时间
+            let percent =
+                sample::sample(&before, &sampled(&after)?, started.elapsed(), sys.clk_tck);
+            (after, CpuMode::Sampled { interval, percent })
+        }
+    };
+    if let Some(tree_options) = &options.pstree {
+        return print_pstree(out, source, &processes, tree_options);
+    }
+    let mut processes: Vec<Process> = processes
+        .into_iter()
+        .filter(|process| options.selection.matches(process, &caller))
+        .collect();
+
+    let ctx = Context {
+        sys,
+        now: source.now(),
+        cpu,
+        forest: None,
+    };
+    if let Some(by) = options.group {
+        let groups = group::group(&processes, by, &ctx);
+        group::print_groups(out, &groups, by, options.output, options.headers)?;
+        return Ok(());
+    }
+    let mut fields = options.fields();
+    ctx.label_headers(&mut fields);
+    // 线程行和进程一样参与排序；--forest 按进程建树，排好后再展开线程
+    if threads && !options.forest {
+        processes = tasks(source, &processes, &ctx.sys)?;
+    }
+    format::sort(&mut processes, &options.sort, &ctx);
+    // 兄弟进程之间保持排序后的顺序
+    let ctx = if options.forest {
+        let (ordered, depth) = tree::forest(&processes);
+        processes = ordered;
+        Context {
+            forest: Some(depth),
+            ..ctx
+        }
+    } else {
+        ctx
+    };
+    if threads && options.forest {
+        processes = tasks(source, &processes, &ctx.sys)?;
+    }
+    match options.output {
+        OutputFormat::Table => {
+            format::print_table(out, &fields, &processes, &ctx, options.headers)?
+        }
+        OutputFormat::Json => export::write_json(out, &processes, &ctx)?,
+        OutputFormat::Csv => export::write_csv(out, &processes, &ctx, options.headers)?,
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+/// -L、-T：把每个进程展开为它的线程
+fn tasks(
+    source: &dyn ProcessSource,
+    processes: &[Process],
+    sys: &SystemInfo,
+) -> io::Result<Vec<Process>> {
+    let mut tasks = vec![];
+    for process in processes {
+        tasks.extend(source.tasks(process, sys)?);
+    }
+    Ok(tasks)
+}
+
+// --------------------------------------------------
+/// --pstree：画出所有进程，不受选择条件影响
+fn print_pstree(
+    out: &mut impl Write,
+    source: &dyn ProcessSource,
+    processes: &[Process],
+    options: &PstreeOptions,
+) -> Result<(), PsError> {
+    let threads: HashMap<i32, Vec<(i32, String)>> = processes
+        .iter()
+        .filter(|process| process.threads > 1)
+        .map(|process| {
+            let threads = source.threads(process.pid).unwrap_or_default();
+            (process.pid, threads)
+        })
+        .collect();
+    for line in tree::pstree(processes, &threads, options) {
+        writeln!(out, "{}", line)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// --------------------------------------------------
+/// 取得运行 ps 的用户和终端
+fn caller(source: &dyn ProcessSource, sys: &SystemInfo, users: &Users) -> io::Result<Caller> {
+    let me = source.process(source.current_pid(), sys, users)?;
+    Ok(Caller {
+        uid: me.uid,
+        tty_nr: me.tty_nr,
+    })
+}
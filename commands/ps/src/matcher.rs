@@ -0,0 +1,164 @@
+//! pgrep 和 pkill 的进程匹配
+//!
+//! 用户、父进程和终端等列表沿用 ps 的 [`Selection`]，但和 ps 不同，这里
+//! 所有条件必须同时满足，见 [`Selection::matches_every`]。
+
+use crate::error::PsError;
+use crate::process::Process;
+use crate::select::Selection;
+use regex::{Regex, RegexBuilder};
+
+/// 匹配条件，没有设置的条件不参与匹配
+#[derive(Debug, Clone, Default)]
+pub struct Matcher {
+    /// 进程名的正则表达式
+    pub pattern: Option<Regex>,
+    /// -f：用完整命令行匹配，而不是进程名
+    pub full: bool,
+    /// -u、-P、-t 给出的有效用户、父进程和终端
+    pub selection: Selection,
+    /// -v：反转匹配结果
+    pub inverse: bool,
+    /// -n：只选最新启动的一个
+    pub newest: bool,
+    /// -o：只选最早启动的一个
+    pub oldest: bool,
+}
+
+impl Matcher {
+    // --------------------------------------------------
+    /// 编译进程名模式。exact 要求匹配整个名字，ignore_case 忽略大小写
+    pub fn compile(pattern: &str, exact: bool, ignore_case: bool) -> Result<Regex, PsError> {
+        let pattern = if exact {
+            format!("^(?:{})$", pattern)
+        } else {
+            pattern.to_string()
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| PsError::Usage(format!("无效的模式: {}", e)))
+    }
+
+    // --------------------------------------------------
+    pub fn matches(&self, process: &Process) -> bool {
+        let pattern = self.pattern.as_ref().is_none_or(|pattern| {
+            if self.full {
+                pattern.is_match(&process.command())
+            } else {
+                pattern.is_match(&process.name)
+            }
+        });
+        (pattern && self.selection.matches_every(process)) != self.inverse
+    }
+
+    // --------------------------------------------------
+    /// 选出匹配的进程，跳过 exclude（pgrep 自己）
+    pub fn select(&self, processes: &[Process], exclude: i32) -> Vec<Process> {
+        let mut selected: Vec<Process> = processes
+            .iter()
+            .filter(|process| process.pid != exclude && self.matches(process))
+            .cloned()
+            .collect();
+        let by_start = |p: &Process| (p.start_time, p.pid);
+        if self.newest {
+            selected = selected
+                .into_iter()
+                .max_by_key(by_start)
+                .into_iter()
+                .collect();
+        } else if self.oldest {
+            selected = selected
+                .into_iter()
+                .min_by_key(by_start)
+                .into_iter()
+                .collect();
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn processes() -> Vec<Process> {
+        let process = |pid, ppid, name: &str, uid, started| Process {
+            ppid,
+            uid,
+            start_time: SystemTime::UNIX_EPOCH + Duration::from_secs(started),
+            cmdline: vec![format!("/usr/sbin/{}", name), "-D".to_string()],
+            ..Process::fixture(pid, name)
+        };
+        vec![
+            process(1, 0, "init", 0, 0),
+            process(10, 1, "sshd", 0, 5),
+            process(20, 10, "sshd", 1000, 9),
+            process(30, 20, "bash", 1000, 7),
+        ]
+    }
+
+    fn pids(matcher: &Matcher, exclude: i32) -> Vec<i32> {
+        matcher
+            .select(&processes(), exclude)
+            .iter()
+            .map(|p| p.pid)
+            .collect()
+    }
+
+    #[test]
+    fn matches_names_and_command_lines() {
+        let mut matcher = Matcher {
+            pattern: Some(Matcher::compile("ssh", false, false).unwrap()),
+            ..Matcher::default()
+        };
+        assert_eq!(pids(&matcher, 0), vec![10, 20]);
+        assert_eq!(pids(&matcher, 20), vec![10]);
+
+        matcher.pattern = Some(Matcher::compile("SSH", true, false).unwrap());
+        assert!(pids(&matcher, 0).is_empty());
+        matcher.pattern = Some(Matcher::compile("SSHD", true, true).unwrap());
+        assert_eq!(pids(&matcher, 0), vec![10, 20]);
+
+        matcher.pattern = Some(Matcher::compile("sbin/.* -D", false, false).unwrap());
+        assert!(pids(&matcher, 0).is_empty());
+        matcher.full = true;
+        assert_eq!(pids(&matcher, 0), vec![1, 10, 20, 30]);
+        assert!(Matcher::compile("(", false, false).is_err());
+    }
+
+    #[test]
+    fn combines_criteria() {
+        let matcher = Matcher {
+            pattern: Some(Matcher::compile("sshd", false, false).unwrap()),
+            selection: Selection {
+                uids: vec![1000],
+                ..Selection::default()
+            },
+            ..Matcher::default()
+        };
+        assert_eq!(pids(&matcher, 0), vec![20]);
+
+        let matcher = Matcher {
+            selection: Selection {
+                ppids: vec![1, 10],
+                ..Selection::default()
+            },
+            inverse: true,
+            ..Matcher::default()
+        };
+        assert_eq!(pids(&matcher, 0), vec![1, 30]);
+
+        let newest = Matcher {
+            newest: true,
+            ..Matcher::default()
+        };
+        assert_eq!(pids(&newest, 0), vec![20]);
+        let oldest = Matcher {
+            oldest: true,
+            ..Matcher::default()
+        };
+        assert_eq!(pids(&oldest, 1), vec![10]);
+    }
+}
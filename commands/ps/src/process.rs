@@ -0,0 +1,258 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// 进程状态，对应 /proc/[pid]/stat 的第三个字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Running,
+    Sleeping,
+    DiskSleep,
+    Zombie,
+    Stopped,
+    TracingStop,
+    Dead,
+    Idle,
+    Other(char),
+}
+
+impl State {
+    pub fn from_code(code: char) -> State {
+        match code {
+            'R' => State::Running,
+            'S' => State::Sleeping,
+            'D' => State::DiskSleep,
+            'Z' => State::Zombie,
+            'T' => State::Stopped,
+            't' => State::TracingStop,
+            'X' | 'x' => State::Dead,
+            'I' => State::Idle,
+            other => State::Other(other),
+        }
+    }
+
+    /// ps 的 STAT 列使用的单字符代码
+    pub fn code(&self) -> char {
+        match self {
+            State::Running => 'R',
+            State::Sleeping => 'S',
+            State::DiskSleep => 'D',
+            State::Zombie => 'Z',
+            State::Stopped => 'T',
+            State::TracingStop => 't',
+            State::Dead => 'X',
+            State::Idle => 'I',
+            State::Other(code) => *code,
+        }
+    }
+}
+
+/// 从 /proc/[pid] 下读出的一个进程
+#[derive(Debug, Clone, PartialEq)]
+pub struct Process {
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub session: i32,
+    /// 控制终端的设备号，0 表示没有终端
+    pub tty_nr: u32,
+    /// 终端前台进程组
+    pub tpgid: i32,
+    /// stat 中括号里的进程名，最多 15 个字符
+    pub name: String,
+    pub state: State,
+    pub uid: u32,
+    pub gid: u32,
+    /// uid 对应的用户名，找不到时为 uid 本身
+    pub user: String,
+    pub priority: i64,
+    pub nice: i64,
+    pub threads: u64,
+    /// 用户态和内核态 CPU 时间，单位为时钟滴答
+    pub utime: u64,
+    pub stime: u64,
+    /// 启动时间
+    pub start_time: SystemTime,
+    /// 虚拟内存大小，单位 KiB
+    pub vsz: u64,
+    /// 常驻内存大小，单位 KiB
+    pub rss: u64,
+    /// 完整命令行，内核线程为空
+    pub cmdline: Vec<String>,
+    /// 环境变量（KEY=VALUE），没有权限读取时为空
+    pub environ: Vec<String>,
+    /// 当前目录，没有权限读取时为 None
+    pub cwd: Option<PathBuf>,
+    /// 可执行文件，内核线程和没有权限读取时为 None
+    pub exe: Option<PathBuf>,
+    /// 所在的 cgroup 路径，例如 /system.slice/sshd.service，读不到时为 None
+    pub cgroup: Option<String>,
+    /// -L、-T 列出线程时的线程号，进程本身的一行为 None
+    pub tid: Option<i32>,
+    /// 最近一次运行所在的 CPU，来自 stat 的 processor 字段
+    pub processor: Option<u32>,
+}
+
+impl Process {
+    /// 终端名，例如 pts/0、tty1，没有终端时为 None
+    pub fn tty(&self) -> Option<String> {
+        tty_name(self.tty_nr)
+    }
+
+    /// 命令行，内核线程等没有命令行的进程用 [name] 表示
+    pub fn command(&self) -> String {
+        if self.cmdline.is_empty() {
+            format!("[{}]", self.name)
+        } else {
+            self.cmdline.join(" ")
+        }
+    }
+
+    /// BSD 风格的 STAT：状态加上 <、N、s、l、+ 等标记
+    pub fn stat(&self) -> String {
+        let mut stat = self.state.code().to_string();
+        if self.nice < 0 {
+            stat.push('<');
+        } else if self.nice > 0 {
+            stat.push('N');
+        }
+        if self.session == self.pid {
+            stat.push('s');
+        }
+        if self.threads > 1 {
+            stat.push('l');
+        }
+        if self.tty_nr != 0 && self.tpgid == self.pgrp {
+            stat.push('+');
+        }
+        stat
+    }
+
+    /// 线程行为线程号，进程行为进程号。两者在同一个编号空间中
+    pub fn task_id(&self) -> i32 {
+        self.tid.unwrap_or(self.pid)
+    }
+
+    /// 所属的 systemd unit，例如 sshd.service
+    pub fn unit(&self) -> Option<&str> {
+        unit_name(self.cgroup.as_deref()?)
+    }
+
+    /// 累计占用的 CPU 时间
+    pub fn cpu_time(&self, clk_tck: u64) -> Duration {
+        let ticks = self.utime + self.stime;
+        Duration::from_millis(ticks * 1000 / clk_tck.max(1))
+    }
+
+    /// 进程整个生命周期的平均 CPU 占用率，和 procps 的 %CPU 相同
+    pub fn cpu_percent(&self, clk_tck: u64, now: SystemTime) -> f64 {
+        let elapsed = now
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+            .as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        self.cpu_time(clk_tck).as_secs_f64() * 100.0 / elapsed
+    }
+}
+
+// --------------------------------------------------
+/// 把 tty_nr 设备号翻译成终端名
+pub fn tty_name(tty_nr: u32) -> Option<String> {
+    if tty_nr == 0 {
+        return None;
+    }
+    let major = (tty_nr >> 8) & 0xfff;
+    let minor = (tty_nr & 0xff) | ((tty_nr >> 12) & 0xfff00);
+    Some(match major {
+        4 if minor < 64 => format!("tty{}", minor),
+        4 => format!("ttyS{}", minor - 64),
+        136..=143 => format!("pts/{}", (major - 136) * 256 + minor),
+        _ => format!("{}:{}", major, minor),
+    })
+}
+
+// --------------------------------------------------
+/// 从 cgroup 路径中找出 systemd unit：取最深的一层 .service 或 .scope，
+/// 都没有时取最深的 .slice。docker 和 podman 在 systemd 下也以 scope
+/// 的形式出现，例如 docker-<id>.scope
+pub fn unit_name(path: &str) -> Option<&str> {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    components
+        .iter()
+        .rev()
+        .find(|c| c.ends_with(".service") || c.ends_with(".scope"))
+        .or_else(|| components.iter().rev().find(|c| c.ends_with(".slice")))
+        .copied()
+}
+
+#[cfg(test)]
+impl Process {
+    /// 测试用的进程，除进程号和名字外都是固定值
+    pub(crate) fn fixture(pid: i32, name: &str) -> Process {
+        Process {
+            pid,
+            ppid: 1,
+            pgrp: pid,
+            session: pid,
+            tty_nr: 0,
+            tpgid: -1,
+            name: name.to_string(),
+            state: State::Sleeping,
+            uid: 0,
+            gid: 0,
+            user: "root".to_string(),
+            priority: 20,
+            nice: 0,
+            threads: 1,
+            utime: 0,
+            stime: 0,
+            start_time: SystemTime::UNIX_EPOCH,
+            vsz: 0,
+            rss: 0,
+            cmdline: vec![],
+            environ: vec![],
+            cwd: None,
+            exe: None,
+            cgroup: None,
+            tid: None,
+            processor: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_ttys() {
+        assert_eq!(tty_name(0), None);
+        assert_eq!(tty_name(0x8800), Some("pts/0".to_string()));
+        assert_eq!(tty_name(0x8803), Some("pts/3".to_string()));
+        assert_eq!(tty_name(0x0401), Some("tty1".to_string()));
+        assert_eq!(tty_name(0x0440), Some("ttyS0".to_string()));
+    }
+
+    #[test]
+    fn names_units() {
+        assert_eq!(
+            unit_name("/system.slice/sshd.service"),
+            Some("sshd.service")
+        );
+        assert_eq!(
+            unit_name("/user.slice/user-1000.slice/user@1000.service/app.slice/vte-spawn-1.scope"),
+            Some("vte-spawn-1.scope")
+        );
+        assert_eq!(
+            unit_name("/system.slice/docker-0123abcd.scope"),
+            Some("docker-0123abcd.scope")
+        );
+        assert_eq!(
+            unit_name("/user.slice/user-1000.slice"),
+            Some("user-1000.slice")
+        );
+        assert_eq!(unit_name("/docker/0123abcd"), None);
+        assert_eq!(unit_name("/"), None);
+    }
+}
@@ -0,0 +1,620 @@
+//! /proc 文件系统的解析
+
+use crate::process::{Process, State};
+use crate::users::Users;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 与单个进程无关的系统信息
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemInfo {
+    /// 系统启动的时刻，来自 /proc/stat 的 btime
+    pub boot_time: SystemTime,
+    /// 已运行时间，来自 /proc/uptime
+    pub uptime: Duration,
+    /// 每秒的时钟滴答数
+    pub clk_tck: u64,
+    /// 内存页大小，单位字节
+    pub page_size: u64,
+    /// 物理内存总量，单位 KiB
+    pub mem_total: u64,
+}
+
+impl SystemInfo {
+    pub fn read(root: &Path) -> io::Result<SystemInfo> {
+        let stat = fs::read_to_string(root.join("stat"))?;
+        let btime = find_value(&stat, "btime").ok_or_else(|| invalid("/proc/stat 中没有 btime"))?;
+        let meminfo = fs::read_to_string(root.join("meminfo"))?;
+        let mem_total = find_value(&meminfo, "MemTotal:")
+            .ok_or_else(|| invalid("/proc/meminfo 中没有 MemTotal"))?;
+        let uptime = fs::read_to_string(root.join("uptime"))?;
+        let uptime = uptime
+            .split_whitespace()
+            .next()
+            .and_then(|secs| secs.parse::<f64>().ok())
+            .ok_or_else(|| invalid("无法解析 /proc/uptime"))?;
+
+        Ok(SystemInfo {
+            boot_time: UNIX_EPOCH + Duration::from_secs(btime),
+            uptime: Duration::from_secs_f64(uptime),
+            clk_tck: sysconf(libc::_SC_CLK_TCK, 100),
+            page_size: sysconf(libc::_SC_PAGESIZE, 4096),
+            mem_total,
+        })
+    }
+}
+
+// --------------------------------------------------
+/// 查询 sysconf，失败时使用默认值
+fn sysconf(name: libc::c_int, default: u64) -> u64 {
+    // SAFETY: sysconf 只读取系统配置，没有任何前置条件
+    let value = unsafe { libc::sysconf(name) };
+    if value > 0 {
+        value as u64
+    } else {
+        default
+    }
+}
+
+// --------------------------------------------------
+/// 在 "key value" 形式的多行文本中找到 key 对应的第一个数字
+fn find_value(text: &str, key: &str) -> Option<u64> {
+    text.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        if words.next()? == key {
+            words.next()?.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+// --------------------------------------------------
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// /proc/stat 中一个 CPU 的累计时间，单位为时钟滴答
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    /// 从 earlier 到现在的占用率
+    pub fn usage_since(&self, earlier: &CpuTimes) -> f64 {
+        let total = self.total.saturating_sub(earlier.total);
+        if total == 0 {
+            return 0.0;
+        }
+        self.busy.saturating_sub(earlier.busy) as f64 * 100.0 / total as f64
+    }
+}
+
+// --------------------------------------------------
+/// 解析 /proc/stat 中 cpu0、cpu1 ... 各行，不包括汇总的 cpu 行。
+/// idle 和 iowait 算作空闲，其余都算作忙碌
+pub fn parse_cpu_times(text: &str) -> Vec<CpuTimes> {
+    text.lines()
+        .filter(|line| line.starts_with("cpu") && !line.starts_with("cpu "))
+        .map(|line| {
+            let ticks: Vec<u64> = line
+                .split_whitespace()
+                .skip(1)
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            // guest 和 guest_nice 已经包含在 user 和 nice 中
+            let total: u64 = ticks.iter().take(8).sum();
+            let idle = ticks.get(3).copied().unwrap_or(0) + ticks.get(4).copied().unwrap_or(0);
+            CpuTimes {
+                busy: total - idle,
+                total,
+            }
+        })
+        .collect()
+}
+
+/// /proc/meminfo 中的内存和交换区，单位 KiB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Memory {
+    pub total: u64,
+    pub available: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+// --------------------------------------------------
+pub fn parse_meminfo(text: &str) -> Memory {
+    let value = |key| find_value(text, key).unwrap_or(0);
+    Memory {
+        total: value("MemTotal:"),
+        available: value("MemAvailable:"),
+        swap_total: value("SwapTotal:"),
+        swap_free: value("SwapFree:"),
+    }
+}
+
+// --------------------------------------------------
+/// /proc/loadavg 的 1、5、15 分钟平均负载
+pub fn parse_loadavg(text: &str) -> io::Result<[f64; 3]> {
+    let mut loads = text.split_whitespace().map(|n| n.parse::<f64>());
+    match (loads.next(), loads.next(), loads.next()) {
+        (Some(Ok(one)), Some(Ok(five)), Some(Ok(fifteen))) => Ok([one, five, fifteen]),
+        _ => Err(invalid(format!("loadavg 无效: {}", text.trim()))),
+    }
+}
+
+/// 整个系统的负载，--top 每次刷新时读取
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Load {
+    pub cpus: Vec<CpuTimes>,
+    pub memory: Memory,
+    pub loadavg: [f64; 3],
+}
+
+impl Load {
+    pub fn read(root: &Path) -> io::Result<Load> {
+        Ok(Load {
+            cpus: parse_cpu_times(&fs::read_to_string(root.join("stat"))?),
+            memory: parse_meminfo(&fs::read_to_string(root.join("meminfo"))?),
+            loadavg: parse_loadavg(&fs::read_to_string(root.join("loadavg"))?)?,
+        })
+    }
+}
+
+/// /proc/[pid]/cgroup 中的一行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cgroup {
+    /// 层级编号，cgroup v2 为 0
+    pub hierarchy: u32,
+    /// 控制器，例如 cpu、memory；v2 和 name=systemd 这样的命名层级为空或只有名字
+    pub controllers: Vec<String>,
+    pub path: String,
+}
+
+// --------------------------------------------------
+/// 解析 /proc/[pid]/cgroup，每行为 hierarchy-ID:controller-list:path。
+/// 路径本身可能包含 ':'，所以只按前两个 ':' 切分
+pub fn parse_cgroup(text: &str) -> Vec<Cgroup> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let hierarchy = parts.next()?.parse().ok()?;
+            let controllers = parts
+                .next()?
+                .split(',')
+                .filter(|c| !c.is_empty())
+                .map(str::to_string)
+                .collect();
+            let path = parts.next()?.to_string();
+            Some(Cgroup {
+                hierarchy,
+                controllers,
+                path,
+            })
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+/// 选出代表进程的 cgroup 路径：优先 v2 的统一层级，其次 v1 中 systemd
+/// 的 name=systemd 层级，最后是第一个控制器的层级
+pub fn cgroup_path(cgroups: &[Cgroup]) -> Option<String> {
+    cgroups
+        .iter()
+        .find(|c| c.hierarchy == 0 && c.controllers.is_empty())
+        .or_else(|| {
+            cgroups
+                .iter()
+                .find(|c| c.controllers.iter().any(|name| name == "name=systemd"))
+        })
+        .or_else(|| cgroups.first())
+        .map(|c| c.path.clone())
+}
+
+/// /proc/[pid]/stat 中 ps 用得到的字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stat {
+    pub pid: i32,
+    pub name: String,
+    pub state: char,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub session: i32,
+    pub tty_nr: u32,
+    pub tpgid: i32,
+    pub utime: u64,
+    pub stime: u64,
+    pub priority: i64,
+    pub nice: i64,
+    pub threads: u64,
+    pub start_ticks: u64,
+    /// 最近一次运行所在的 CPU，老内核的 stat 中没有这个字段
+    pub processor: Option<u32>,
+}
+
+// --------------------------------------------------
+/// 解析 /proc/[pid]/stat。进程名可能包含空格和括号，所以以最后一个 ')'
+/// 为界
+pub fn parse_stat(text: &str) -> io::Result<Stat> {
+    let open = text.find('(').ok_or_else(|| invalid("stat 缺少进程名"))?;
+    let close = text.rfind(')').ok_or_else(|| invalid("stat 缺少进程名"))?;
+    let pid = text[..open]
+        .trim()
+        .parse()
+        .map_err(|_| invalid("pid 无效"))?;
+    let name = text[open + 1..close].to_string();
+    let fields: Vec<&str> = text[close + 1..].split_whitespace().collect();
+    if fields.len() < 20 {
+        return Err(invalid(format!("stat 字段太少: {}", text.trim())));
+    }
+    let field = |i: usize| -> io::Result<i64> {
+        fields[i]
+            .parse()
+            .map_err(|_| invalid(format!("stat 字段无效: {}", fields[i])))
+    };
+
+    Ok(Stat {
+        pid,
+        name,
+        state: fields[0].chars().next().unwrap_or('?'),
+        ppid: field(1)? as i32,
+        pgrp: field(2)? as i32,
+        session: field(3)? as i32,
+        tty_nr: field(4)? as u32,
+        tpgid: field(5)? as i32,
+        utime: field(11)? as u64,
+        stime: field(12)? as u64,
+        priority: field(15)?,
+        nice: field(16)?,
+        threads: field(17)? as u64,
+        start_ticks: field(19)? as u64,
+        processor: fields.get(36).and_then(|cpu| cpu.parse().ok()),
+    })
+}
+
+// --------------------------------------------------
+/// 从 /proc/[pid]/status 取出有效 uid 和 gid
+pub fn parse_status(text: &str) -> io::Result<(u32, u32)> {
+    let id = |key: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|ids| ids.split_whitespace().nth(1))
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| invalid(format!("status 中没有 {}", key)))
+    };
+    Ok((id("Uid:")?, id("Gid:")?))
+}
+
+// --------------------------------------------------
+/// 解析 /proc/[pid]/statm，返回 (虚拟内存页数, 常驻内存页数)
+pub fn parse_statm(text: &str) -> io::Result<(u64, u64)> {
+    let mut pages = text.split_whitespace().map(|n| n.parse::<u64>());
+    match (pages.next(), pages.next()) {
+        (Some(Ok(size)), Some(Ok(resident))) => Ok((size, resident)),
+        _ => Err(invalid(format!("statm 无效: {}", text.trim()))),
+    }
+}
+
+// --------------------------------------------------
+/// cmdline 和 environ 都是以 '\0' 结尾的字符串列表。只去掉最后一个
+/// '\0'，中间的空字符串是真实的参数，例如 sh -c ""
+pub fn parse_nul_separated(bytes: &[u8]) -> Vec<String> {
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+    bytes
+        .strip_suffix(b"\0")
+        .unwrap_or(bytes)
+        .split(|&b| b == 0)
+        .map(|item| String::from_utf8_lossy(item).into_owned())
+        .collect()
+}
+
+// --------------------------------------------------
+/// 列出 /proc 下所有的进程号
+pub fn pids(root: &Path) -> io::Result<Vec<i32>> {
+    let mut pids: Vec<i32> = fs::read_dir(root)?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    pids.sort_unstable();
+    Ok(pids)
+}
+
+// --------------------------------------------------
+/// 读取单个进程。environ、cwd 和 exe 通常只有进程的属主才能读，读不到
+/// 时留空
+pub fn read_process(root: &Path, pid: i32, sys: &SystemInfo, users: &Users) -> io::Result<Process> {
+    let dir = root.join(pid.to_string());
+    let stat = parse_stat(&fs::read_to_string(dir.join("stat"))?)?;
+    let (uid, gid) = parse_status(&fs::read_to_string(dir.join("status"))?)?;
+    let (size, resident) = parse_statm(&fs::read_to_string(dir.join("statm"))?)?;
+    let cmdline = parse_nul_separated(&fs::read(dir.join("cmdline"))?);
+    let environ = fs::read(dir.join("environ"))
+        .map(|bytes| parse_nul_separated(&bytes))
+        .unwrap_or_default();
+    let cwd = fs::read_link(dir.join("cwd")).ok();
+    let exe = fs::read_link(dir.join("exe")).ok();
+    let cgroup = fs::read_to_string(dir.join("cgroup"))
+        .ok()
+        .and_then(|text| cgroup_path(&parse_cgroup(&text)));
+    let page_kb = sys.page_size / 1024;
+    let started = Duration::from_millis(stat.start_ticks * 1000 / sys.clk_tck);
+
+    Ok(Process {
+        pid: stat.pid,
+        ppid: stat.ppid,
+        pgrp: stat.pgrp,
+        session: stat.session,
+        tty_nr: stat.tty_nr,
+        tpgid: stat.tpgid,
+        name: stat.name,
+        state: State::from_code(stat.state),
+        uid,
+        gid,
+        user: users.name(uid),
+        priority: stat.priority,
+        nice: stat.nice,
+        threads: stat.threads,
+        utime: stat.utime,
+        stime: stat.stime,
+        start_time: sys.boot_time + started,
+        vsz: size * page_kb,
+        rss: resident * page_kb,
+        cmdline,
+        environ,
+        cwd,
+        exe,
+        cgroup,
+        tid: None,
+        processor: stat.processor,
+    })
+}
+
+// --------------------------------------------------
+/// 读取所有进程。列目录和读取之间退出的进程直接跳过
+pub fn processes(root: &Path, sys: &SystemInfo, users: &Users) -> io::Result<Vec<Process>> {
+    let mut processes = vec![];
+    for pid in pids(root)? {
+        match read_process(root, pid, sys, users) {
+            Ok(process) => processes.push(process),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            // 进程退出后读取 /proc/[pid] 下的文件会得到 ESRCH
+            Err(e) if e.raw_os_error() == Some(libc::ESRCH) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(processes)
+}
+
+// --------------------------------------------------
+/// 进程除主线程外的线程号和线程名，来自 /proc/[pid]/task/[tid]/comm
+pub fn threads(root: &Path, pid: i32) -> io::Result<Vec<(i32, String)>> {
+    let dir = root.join(pid.to_string()).join("task");
+    let mut threads = vec![];
+    for tid in pids(&dir)? {
+        if tid == pid {
+            continue;
+        }
+        match fs::read_to_string(dir.join(tid.to_string()).join("comm")) {
+            Ok(name) => threads.push((tid, name.trim_end().to_string())),
+            // 线程可能已经退出
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(threads)
+}
+
+// --------------------------------------------------
+/// -L、-T：进程的每个线程一行，包括主线程。线程名、状态、CPU 时间、启动
+/// 时间和所在的 CPU 来自 /proc/[pid]/task/[tid]/stat，其余字段和进程相同。
+/// 没有 task 目录时把进程本身当作唯一的线程
+pub fn tasks(root: &Path, process: &Process, sys: &SystemInfo) -> io::Result<Vec<Process>> {
+    let dir = root.join(process.pid.to_string()).join("task");
+    let tids = match pids(&dir) {
+        Ok(tids) => tids,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(vec![Process {
+                tid: Some(process.pid),
+                ..process.clone()
+            }]);
+        }
+        Err(e) => return Err(e),
+    };
+    let mut tasks = vec![];
+    for tid in tids {
+        let stat = match fs::read_to_string(dir.join(tid.to_string()).join("stat")) {
+            Ok(text) => parse_stat(&text)?,
+            // 线程可能已经退出
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) if e.raw_os_error() == Some(libc::ESRCH) => continue,
+            Err(e) => return Err(e),
+        };
+        let started = Duration::from_millis(stat.start_ticks * 1000 / sys.clk_tck);
+        tasks.push(Process {
+            name: stat.name,
+            state: State::from_code(stat.state),
+            priority: stat.priority,
+            nice: stat.nice,
+            utime: stat.utime,
+            stime: stat.stime,
+            start_time: sys.boot_time + started,
+            tid: Some(tid),
+            processor: stat.processor,
+            ..process.clone()
+        });
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stat() {
+        let text = "1234 (tmux: server) S 1 1234 1234 34816 1234 4194368 \
+                    931 0 0 0 152 61 0 0 20 0 1 0 8765 12345678 456 \
+                    18446744073709551615\n";
+        let stat = parse_stat(text).unwrap();
+        assert_eq!(stat.pid, 1234);
+        assert_eq!(stat.name, "tmux: server");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.ppid, 1);
+        assert_eq!(stat.tty_nr, 34816);
+        assert_eq!((stat.utime, stat.stime), (152, 61));
+        assert_eq!((stat.priority, stat.nice), (20, 0));
+        assert_eq!(stat.threads, 1);
+        assert_eq!(stat.start_ticks, 8765);
+        assert_eq!(stat.processor, None);
+    }
+
+    #[test]
+    fn parses_processor() {
+        let text = "77 (tokio-runtime-w) R 1 70 70 0 -1 4194368 0 0 0 0 300 20 0 0 \
+                    20 0 8 0 5000 0 0 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 \
+                    -1 3 0 0 0 0 0\n";
+        let stat = parse_stat(text).unwrap();
+        assert_eq!(stat.processor, Some(3));
+    }
+
+    #[test]
+    fn reads_tasks() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/inputs/proc");
+        let sys = SystemInfo {
+            boot_time: UNIX_EPOCH,
+            uptime: Duration::from_secs(90000),
+            clk_tck: 100,
+            page_size: 4096,
+            mem_total: 2048000,
+        };
+        let worker = Process {
+            threads: 3,
+            ..Process::fixture(410, "worker")
+        };
+        let tasks = tasks(&root, &worker, &sys).unwrap();
+        let summary: Vec<_> = tasks
+            .iter()
+            .map(|t| (t.pid, t.tid, t.name.as_str(), t.state.code(), t.processor))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (410, Some(410), "worker", 'R', Some(0)),
+                (410, Some(411), "tokio-runtime-w", 'R', Some(0)),
+                (410, Some(412), "tokio-runtime-w", 'S', Some(1)),
+            ]
+        );
+        assert_eq!((tasks[1].utime, tasks[1].stime), (1500, 400));
+        assert!(tasks.iter().all(|t| t.threads == 3));
+
+        // 没有 task 目录时进程本身是唯一的线程
+        let sleep = Process::fixture(400, "sleep");
+        let tasks = super::tasks(&root, &sleep, &sys).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].tid, Some(400));
+    }
+
+    #[test]
+    fn parses_stat_with_parens_in_name() {
+        let text = "42 (a) b) R 1 42 42 0 -1 0 0 0 0 0 5 6 0 0 20 0 3 0 99 0 0";
+        let stat = parse_stat(text).unwrap();
+        assert_eq!(stat.name, "a) b");
+        assert_eq!(stat.state, 'R');
+        assert_eq!(stat.threads, 3);
+    }
+
+    #[test]
+    fn parses_status() {
+        let text = "Name:\tbash\nUid:\t1000\t1001\t1000\t1000\n\
+                    Gid:\t100\t100\t100\t100\n";
+        assert_eq!(parse_status(text).unwrap(), (1001, 100));
+        assert!(parse_status("Name:\tbash\n").is_err());
+    }
+
+    #[test]
+    fn parses_system_load() {
+        let stat = "cpu  30 0 10 60 0 0 0 0 0 0\n\
+                    cpu0 20 0 5 25 0 0 0 0 0 0\n\
+                    cpu1 10 0 5 30 5 0 0 0 0 0\n\
+                    intr 1 2 3\n";
+        let cpus = parse_cpu_times(stat);
+        assert_eq!(
+            cpus,
+            vec![
+                CpuTimes {
+                    busy: 25,
+                    total: 50
+                },
+                CpuTimes {
+                    busy: 15,
+                    total: 50
+                }
+            ]
+        );
+        let later = CpuTimes {
+            busy: 25,
+            total: 70,
+        };
+        assert_eq!(later.usage_since(&cpus[1]), 50.0);
+        assert_eq!(later.usage_since(&later), 0.0);
+
+        let meminfo = "MemTotal: 1000 kB\nMemFree: 10 kB\nMemAvailable: 600 kB\n\
+                       SwapTotal: 200 kB\nSwapFree: 50 kB\n";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Memory {
+                total: 1000,
+                available: 600,
+                swap_total: 200,
+                swap_free: 50
+            }
+        );
+        assert_eq!(
+            parse_loadavg("0.51 0.38 0.45 2/71 13053\n").unwrap(),
+            [0.51, 0.38, 0.45]
+        );
+        assert!(parse_loadavg("").is_err());
+    }
+
+    #[test]
+    fn parses_cgroups() {
+        let text =
+            "4:memory:/docker/abc\n1:name=systemd:/system.slice/a:b.service\n0::/user.slice\n";
+        let cgroups = parse_cgroup(text);
+        assert_eq!(cgroups.len(), 3);
+        assert_eq!(cgroups[0].controllers, vec!["memory"]);
+        assert_eq!(cgroups[1].path, "/system.slice/a:b.service");
+        assert_eq!(
+            cgroups[2],
+            Cgroup {
+                hierarchy: 0,
+                controllers: vec![],
+                path: "/user.slice".to_string()
+            }
+        );
+        assert_eq!(cgroup_path(&cgroups).as_deref(), Some("/user.slice"));
+        assert_eq!(
+            cgroup_path(&cgroups[..2]).as_deref(),
+            Some("/system.slice/a:b.service")
+        );
+        assert_eq!(cgroup_path(&cgroups[..1]).as_deref(), Some("/docker/abc"));
+        assert_eq!(cgroup_path(&[]), None);
+    }
+
+    #[test]
+    fn parses_statm_and_cmdline() {
+        assert_eq!(parse_statm("2000 300 100 1 0 50 0\n").unwrap(), (2000, 300));
+        assert_eq!(
+            parse_nul_separated(b"vim\0-u\0NONE\0"),
+            vec!["vim", "-u", "NONE"]
+        );
+        assert_eq!(parse_nul_separated(b"sh\0-c\0\0"), vec!["sh", "-c", ""]);
+        assert_eq!(parse_nul_separated(b"\0"), vec![""]);
+        assert!(parse_nul_separated(b"").is_empty());
+    }
+}
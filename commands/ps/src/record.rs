@@ -0,0 +1,933 @@
+//! --record 和 --replay：把进程快照记录到文件，事后再查看
+//!
+//! 记录文件每行一个 json。第一行是头部（系统信息和记录间隔），之后有两种
+//! 行：
+//! - `{"proc": ...}`：进程的静态信息（用户、命令行、cgroup 等），每个进程
+//!   （进程号加启动时间）只在第一次出现和信息变化时写一次
+//! - `{"frame": ...}`：一帧，只包含计数有变化的进程和这一帧中退出的进程
+//!
+//! 计数保存的是累计 CPU 时间等原始值，%CPU 在回放时按相邻两帧计算，所以
+//! --top 回放和实时查看的结果一样。回放时逐行读取，不把整个文件读入内存。
+//! 每帧写完立即刷新，记录被中断时最后一行可能不完整，读取时忽略。
+
+use crate::error::PsError;
+use crate::export::OutputFormat;
+use crate::process::{Process, State};
+use crate::procfs::{CpuTimes, Load, Memory, SystemInfo};
+use crate::sample;
+use crate::source::ProcessSource;
+use crate::users::Users;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 记录文件的格式版本
+const VERSION: u32 = 2;
+
+/// 记录时等待下一帧，每隔这么久检查一次是否按了 Ctrl-C
+const POLL: Duration = Duration::from_millis(100);
+
+/// 记录文件的第一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u32,
+    pub interval_ms: u64,
+    /// 系统启动时刻，毫秒
+    pub boot_ms: u64,
+    pub clk_tck: u64,
+    pub page_size: u64,
+    pub mem_total: u64,
+}
+
+/// 进程中很少变化的信息。id 由记录程序分配，一个 id 对应一个进程号和启动
+/// 时间，进程号被复用时分配新的 id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Info {
+    pub id: u64,
+    pub pid: i32,
+    pub start_ms: u64,
+    pub session: i32,
+    pub tty_nr: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub user: String,
+    pub name: String,
+    pub cmdline: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<String>,
+}
+
+/// 进程在一帧中的计数，按固定顺序序列化成数组以减小文件：
+/// [id, ppid, pgrp, tpgid, state, priority, nice, threads, utime, stime, vsz,
+///  rss, processor]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Entry(
+    u64,
+    i32,
+    i32,
+    i32,
+    char,
+    i64,
+    i64,
+    u64,
+    u64,
+    u64,
+    u64,
+    u64,
+    Option<u32>,
+);
+
+/// 一帧。p 中只有新出现或计数有变化的进程，其余进程沿用上一帧的值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// 记录时刻，毫秒
+    pub t: u64,
+    /// 每个核心的累计 [忙碌, 总计] 时钟滴答
+    pub cpus: Vec<(u64, u64)>,
+    /// [总量, 可用, 交换区总量, 交换区剩余]，单位 KiB
+    pub mem: [u64; 4],
+    pub load: [f64; 3],
+    pub p: Vec<Entry>,
+    /// 这一帧中已经不存在的进程的 id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit: Vec<u64>,
+}
+
+/// 头部之后的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Line {
+    Proc(Info),
+    Frame(Frame),
+}
+
+// --------------------------------------------------
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// --------------------------------------------------
+fn from_millis(ms: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(ms)
+}
+
+// --------------------------------------------------
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Header {
+    pub fn new(sys: &SystemInfo, interval: Duration) -> Header {
+        Header {
+            version: VERSION,
+            interval_ms: interval.as_millis() as u64,
+            boot_ms: millis(sys.boot_time),
+            clk_tck: sys.clk_tck,
+            page_size: sys.page_size,
+            mem_total: sys.mem_total,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+}
+
+impl Info {
+    pub fn new(id: u64, process: &Process) -> Info {
+        let path = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
+        };
+        Info {
+            id,
+            pid: process.pid,
+            start_ms: millis(process.start_time),
+            session: process.session,
+            tty_nr: process.tty_nr,
+            uid: process.uid,
+            gid: process.gid,
+            user: process.user.clone(),
+            name: process.name.clone(),
+            cmdline: process.cmdline.clone(),
+            cwd: path(&process.cwd),
+            exe: path(&process.exe),
+            cgroup: process.cgroup.clone(),
+        }
+    }
+}
+
+impl Entry {
+    pub fn new(id: u64, process: &Process) -> Entry {
+        Entry(
+            id,
+            process.ppid,
+            process.pgrp,
+            process.tpgid,
+            process.state.code(),
+            process.priority,
+            process.nice,
+            process.threads,
+            process.utime,
+            process.stime,
+            process.vsz,
+            process.rss,
+            process.processor,
+        )
+    }
+
+    fn id(&self) -> u64 {
+        self.0
+    }
+
+    /// 和静态信息合成进程。环境变量不记录，回放时为空
+    pub fn process(&self, info: &Info) -> Process {
+        let Entry(
+            _,
+            ppid,
+            pgrp,
+            tpgid,
+            state,
+            priority,
+            nice,
+            threads,
+            utime,
+            stime,
+            vsz,
+            rss,
+            processor,
+        ) = *self;
+        Process {
+            pid: info.pid,
+            ppid,
+            pgrp,
+            session: info.session,
+            tty_nr: info.tty_nr,
+            tpgid,
+            name: info.name.clone(),
+            state: State::from_code(state),
+            uid: info.uid,
+            gid: info.gid,
+            user: info.user.clone(),
+            priority,
+            nice,
+            threads,
+            utime,
+            stime,
+            start_time: from_millis(info.start_ms),
+            vsz,
+            rss,
+            cmdline: info.cmdline.clone(),
+            environ: vec![],
+            cwd: info.cwd.as_ref().map(PathBuf::from),
+            exe: info.exe.as_ref().map(PathBuf::from),
+            cgroup: info.cgroup.clone(),
+            tid: None,
+            processor,
+        }
+    }
+}
+
+impl Frame {
+    fn new(time: SystemTime, load: &Load) -> Frame {
+        let memory = &load.memory;
+        Frame {
+            t: millis(time),
+            cpus: load.cpus.iter().map(|cpu| (cpu.busy, cpu.total)).collect(),
+            mem: [
+                memory.total,
+                memory.available,
+                memory.swap_total,
+                memory.swap_free,
+            ],
+            load: load.loadavg,
+            p: vec![],
+            exit: vec![],
+        }
+    }
+
+    pub fn time(&self) -> SystemTime {
+        from_millis(self.t)
+    }
+
+    pub fn load(&self) -> Load {
+        let [total, available, swap_total, swap_free] = self.mem;
+        Load {
+            cpus: self
+                .cpus
+                .iter()
+                .map(|&(busy, total)| CpuTimes { busy, total })
+                .collect(),
+            memory: Memory {
+                total,
+                available,
+                swap_total,
+                swap_free,
+            },
+            loadavg: self.load,
+        }
+    }
+}
+
+/// 记录程序记住的一个进程：写出过的静态信息和上一帧的计数
+struct Known {
+    id: u64,
+    info: Info,
+    entry: Entry,
+}
+
+/// 把快照转换成增量的行
+#[derive(Default)]
+pub struct Recorder {
+    next_id: u64,
+    /// 按 (进程号, 启动时刻) 索引
+    known: HashMap<(i32, u64), Known>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    // --------------------------------------------------
+    /// 一帧对应的行：新的或变化了的静态信息在前，帧在最后
+    pub fn lines(&mut self, time: SystemTime, load: &Load, processes: &[Process]) -> Vec<Line> {
+        let mut lines = vec![];
+        let mut frame = Frame::new(time, load);
+        let mut seen = vec![];
+        for process in processes {
+            let key = (process.pid, millis(process.start_time));
+            let known = match self.known.get_mut(&key) {
+                Some(known) => known,
+                None => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    let info = Info::new(id, process);
+                    lines.push(Line::Proc(info.clone()));
+                    let entry = Entry::new(id, process);
+                    frame.p.push(entry);
+                    self.known.insert(key, Known { id, info, entry });
+                    seen.push(key);
+                    continue;
+                }
+            };
+            // 命令行、cgroup 等也可能变化，例如 setproctitle 和迁移 cgroup
+            let info = Info::new(known.id, process);
+            if info != known.info {
+                lines.push(Line::Proc(info.clone()));
+                known.info = info;
+            }
+            let entry = Entry::new(known.id, process);
+            if entry != known.entry {
+                frame.p.push(entry);
+                known.entry = entry;
+            }
+            seen.push(key);
+        }
+        seen.sort_unstable();
+        self.known.retain(|key, known| {
+            let alive = seen.binary_search(key).is_ok();
+            if !alive {
+                frame.exit.push(known.id);
+            }
+            alive
+        });
+        frame.exit.sort_unstable();
+        lines.push(Line::Frame(frame));
+        lines
+    }
+}
+
+// --------------------------------------------------
+/// 按下 Ctrl-C 后置位，记录在写完当前帧后结束
+static STOP: AtomicBool = AtomicBool::new(false);
+
+// --------------------------------------------------
+fn catch_ctrl_c() -> io::Result<()> {
+    static HANDLER: Once = Once::new();
+    let mut result = Ok(());
+    HANDLER.call_once(|| {
+        result =
+            ctrlc::set_handler(|| STOP.store(true, Ordering::SeqCst)).map_err(io::Error::other);
+    });
+    result
+}
+
+// --------------------------------------------------
+/// 每隔 interval 记录一帧，直到按下 Ctrl-C。结束时在 out 上报告帧数
+pub fn record(
+    out: &mut impl Write,
+    path: &Path,
+    source: &dyn ProcessSource,
+    users: &Users,
+    interval: Duration,
+) -> Result<(), PsError> {
+    catch_ctrl_c()?;
+    STOP.store(false, Ordering::SeqCst);
+    let sys = source.system()?;
+    let mut file = BufWriter::new(File::create(path)?);
+    write_line(&mut file, &Header::new(&sys, interval))?;
+    let mut recorder = Recorder::new();
+    let mut frames = 0;
+    let mut next = Instant::now();
+    while !STOP.load(Ordering::SeqCst) {
+        let processes = source.processes(&sys, users)?;
+        for line in recorder.lines(source.now(), &source.load()?, &processes) {
+            write_line(&mut file, &line)?;
+        }
+        file.flush()?;
+        frames += 1;
+        next += interval;
+        while !STOP.load(Ordering::SeqCst) {
+            let left = next.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            thread::sleep(left.min(POLL));
+        }
+    }
+    writeln!(out, "已记录 {} 帧到 {}", frames, path.display())?;
+    Ok(())
+}
+
+// --------------------------------------------------
+fn write_line(out: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    writeln!(out)
+}
+
+/// 回放到某一帧时所有进程的完整状态
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub time: SystemTime,
+    pub load: Load,
+    pub processes: Vec<Process>,
+}
+
+/// 逐行读取记录，把增量的帧还原成完整的快照
+pub struct Reader<R> {
+    input: R,
+    pub header: Header,
+    /// 已经读过的行数，用于报错
+    line: usize,
+    infos: HashMap<u64, Info>,
+    /// 当前存在的进程，按 id 排列
+    entries: BTreeMap<u64, Entry>,
+}
+
+impl Reader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Reader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    /// 读取并检查头部
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut first = String::new();
+        if input.read_line(&mut first)? == 0 {
+            return Err(invalid("记录文件是空的".to_string()));
+        }
+        let header: Header = serde_json::from_str(&first)
+            .map_err(|e| invalid(format!("记录文件第 1 行无效: {}", e)))?;
+        if header.version != VERSION {
+            return Err(invalid(format!("不支持的记录文件版本 {}", header.version)));
+        }
+        Ok(Reader {
+            input,
+            header,
+            line: 1,
+            infos: HashMap::new(),
+            entries: BTreeMap::new(),
+        })
+    }
+
+    // --------------------------------------------------
+    /// 读到下一帧为止，返回这一帧的完整快照，记录结束时返回 None。
+    /// 没有换行符的最后一行是被中断的写入，当作记录结束
+    pub fn next_frame(&mut self) -> io::Result<Option<Snapshot>> {
+        let mut text = String::new();
+        loop {
+            text.clear();
+            if self.input.read_line(&mut text)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            let line = match serde_json::from_str(&text) {
+                Ok(line) => line,
+                Err(_) if !text.ends_with('\n') => return Ok(None),
+                Err(e) => return Err(invalid(format!("记录文件第 {} 行无效: {}", self.line, e))),
+            };
+            match line {
+                Line::Proc(info) => {
+                    self.infos.insert(info.id, info);
+                }
+                Line::Frame(frame) => return self.apply(frame).map(Some),
+            }
+        }
+    }
+
+    // --------------------------------------------------
+    fn apply(&mut self, frame: Frame) -> io::Result<Snapshot> {
+        for id in &frame.exit {
+            self.entries.remove(id);
+            self.infos.remove(id);
+        }
+        for entry in &frame.p {
+            self.entries.insert(entry.id(), *entry);
+        }
+        let mut processes = vec![];
+        for (id, entry) in &self.entries {
+            let info = self.infos.get(id).ok_or_else(|| {
+                invalid(format!(
+                    "记录文件第 {} 行引用了未知的进程 {}",
+                    self.line, id
+                ))
+            })?;
+            processes.push(entry.process(info));
+        }
+        processes.sort_by_key(|process| process.pid);
+        Ok(Snapshot {
+            time: frame.time(),
+            load: frame.load(),
+            processes,
+        })
+    }
+
+    // --------------------------------------------------
+    /// 系统信息，运行时间按 time 时刻计算
+    pub fn system(&self, time: SystemTime) -> SystemInfo {
+        let header = &self.header;
+        let boot_time = from_millis(header.boot_ms);
+        SystemInfo {
+            boot_time,
+            uptime: time.duration_since(boot_time).unwrap_or_default(),
+            clk_tck: header.clk_tck,
+            page_size: header.page_size,
+            mem_total: header.mem_total,
+        }
+    }
+}
+
+/// 逐帧回放记录。每次读取进程前进一帧，到最后一帧后停住，--top 因此
+/// 按刷新间隔播放整个记录
+pub struct Replay<R> {
+    reader: RefCell<Reader<R>>,
+    /// 当前帧
+    current: RefCell<Snapshot>,
+    /// 第一帧已经交给调用者
+    started: Cell<bool>,
+}
+
+impl<R: BufRead> Replay<R> {
+    /// 读入第一帧，记录中没有帧时出错
+    pub fn new(mut reader: Reader<R>) -> io::Result<Self> {
+        let first = reader
+            .next_frame()?
+            .ok_or_else(|| invalid("记录中没有快照".to_string()))?;
+        Ok(Replay {
+            reader: RefCell::new(reader),
+            current: RefCell::new(first),
+            started: Cell::new(false),
+        })
+    }
+}
+
+impl<R: BufRead> ProcessSource for Replay<R> {
+    fn system(&self) -> io::Result<SystemInfo> {
+        Ok(self.reader.borrow().system(self.current.borrow().time))
+    }
+
+    fn processes(&self, _sys: &SystemInfo, _users: &Users) -> io::Result<Vec<Process>> {
+        if self.started.replace(true) {
+            if let Some(next) = self.reader.borrow_mut().next_frame()? {
+                *self.current.borrow_mut() = next;
+            }
+        }
+        Ok(self.current.borrow().processes.clone())
+    }
+
+    fn load(&self) -> io::Result<Load> {
+        Ok(self.current.borrow().load.clone())
+    }
+
+    fn now(&self) -> SystemTime {
+        self.current.borrow().time
+    }
+
+    fn live(&self) -> bool {
+        false
+    }
+}
+
+/// 一个进程在整个记录中的峰值
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Peak {
+    pub pid: i32,
+    pub user: String,
+    pub name: String,
+    /// 相邻两帧之间的最高 %CPU
+    pub cpu_percent: f64,
+    /// 出现最高 %CPU 的时刻，RFC 3339
+    pub cpu_at: Option<String>,
+    pub rss_kb: u64,
+    pub rss_at: String,
+    /// 出现在多少帧中
+    pub frames: usize,
+    pub command: String,
+}
+
+/// 整个记录的峰值汇总
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub frames: usize,
+    /// 第一帧和最后一帧的时刻，没有帧时为 None
+    pub span: Option<(SystemTime, SystemTime)>,
+    pub interval: Duration,
+    pub peaks: Vec<Peak>,
+}
+
+// --------------------------------------------------
+/// 逐帧统计每个进程的 %CPU 和常驻内存峰值，按 %CPU 从高到低排列。进程号
+/// 被复用时按启动时间区分成不同的进程
+pub fn summarize(reader: &mut Reader<impl BufRead>) -> io::Result<Summary> {
+    let format_time = |time: SystemTime| DateTime::<Local>::from(time).to_rfc3339();
+    let clk_tck = reader.header.clk_tck;
+    let mut peaks: HashMap<(i32, SystemTime), Peak> = HashMap::new();
+    let mut frames = 0;
+    let mut span: Option<(SystemTime, SystemTime)> = None;
+    let mut previous: Option<Snapshot> = None;
+    while let Some(snapshot) = reader.next_frame()? {
+        let time = snapshot.time;
+        frames += 1;
+        span = Some((span.map_or(time, |(start, _)| start), time));
+        let cpu = match &previous {
+            Some(before) => {
+                let elapsed = time.duration_since(before.time).unwrap_or_default();
+                sample::sample(&before.processes, &snapshot.processes, elapsed, clk_tck)
+            }
+            None => HashMap::new(),
+        };
+        for process in &snapshot.processes {
+            let peak = peaks
+                .entry((process.pid, process.start_time))
+                .or_insert_with(|| Peak {
+                    pid: process.pid,
+                    user: process.user.clone(),
+                    name: process.name.clone(),
+                    cpu_percent: 0.0,
+                    cpu_at: None,
+                    rss_kb: process.rss,
+                    rss_at: format_time(time),
+                    frames: 0,
+                    command: process.command(),
+                });
+            peak.frames += 1;
+            if let Some(&percent) = cpu.get(&process.pid) {
+                if peak.cpu_at.is_none() || percent > peak.cpu_percent {
+                    peak.cpu_percent = (percent * 10.0).round() / 10.0;
+                    peak.cpu_at = Some(format_time(time));
+                }
+            }
+            if process.rss > peak.rss_kb {
+                peak.rss_kb = process.rss;
+                peak.rss_at = format_time(time);
+            }
+        }
+        previous = Some(snapshot);
+    }
+    let mut peaks: Vec<Peak> = peaks.into_values().collect();
+    peaks.sort_by(|a, b| {
+        b.cpu_percent
+            .total_cmp(&a.cpu_percent)
+            .then(b.rss_kb.cmp(&a.rss_kb))
+            .then(a.pid.cmp(&b.pid))
+    });
+    Ok(Summary {
+        frames,
+        span,
+        interval: reader.header.interval(),
+        peaks,
+    })
+}
+
+// --------------------------------------------------
+/// 输出峰值汇总。表格中的时刻只显示时分秒
+pub fn print_peaks(
+    out: &mut impl Write,
+    reader: &mut Reader<impl BufRead>,
+    output: OutputFormat,
+    headers: bool,
+) -> io::Result<()> {
+    let summary = summarize(reader)?;
+    match output {
+        OutputFormat::Table => {
+            if let Some((start, end)) = summary.span {
+                let start: DateTime<Local> = start.into();
+                let end: DateTime<Local> = end.into();
+                writeln!(
+                    out,
+                    "{} 帧，{} 至 {}，间隔 {}",
+                    summary.frames,
+                    start.format("%Y-%m-%d %H:%M:%S"),
+                    end.format("%Y-%m-%d %H:%M:%S"),
+                    sample::format_duration(summary.interval)
+                )?;
+            }
+            for line in format_peaks(&summary.peaks, headers) {
+                writeln!(out, "{}", line)?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &summary.peaks)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(headers)
+                .from_writer(&mut *out);
+            for peak in &summary.peaks {
+                writer.serialize(peak)?;
+            }
+            writer.flush()?;
+        }
+    }
+    out.flush()
+}
+
+// --------------------------------------------------
+fn format_peaks(peaks: &[Peak], headers: bool) -> Vec<String> {
+    // rfc3339 中取出时分秒
+    let clock = |time: &str| time.get(11..19).unwrap_or(time).to_string();
+    let mut rows: Vec<[String; 7]> = vec![];
+    if headers {
+        rows.push(["PID", "USER", "%CPU", "AT", "RSS", "AT", "COMMAND"].map(str::to_string));
+    }
+    for peak in peaks {
+        rows.push([
+            peak.pid.to_string(),
+            peak.user.clone(),
+            format!("{:.1}", peak.cpu_percent),
+            peak.cpu_at.as_deref().map_or("-".to_string(), clock),
+            peak.rss_kb.to_string(),
+            clock(&peak.rss_at),
+            peak.command.clone(),
+        ]);
+    }
+    let width = |i: usize| rows.iter().map(|row| row[i].len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..6).map(width).collect();
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row[..6]
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (cell, &w))| {
+                    if i == 1 {
+                        format!("{:<w$}", cell)
+                    } else {
+                        format!("{:>w$}", cell)
+                    }
+                })
+                .collect();
+            format!("{} {}", cells.join(" "), row[6])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, ticks: u64, rss: u64) -> Process {
+        Process {
+            utime: ticks,
+            rss,
+            start_time: from_millis(1_000),
+            cmdline: vec!["cc".to_string(), "-O2".to_string()],
+            ..Process::fixture(pid, "cc")
+        }
+    }
+
+    fn load(secs: u64) -> Load {
+        Load {
+            cpus: vec![CpuTimes {
+                busy: secs * 50,
+                total: secs * 100,
+            }],
+            memory: Memory {
+                total: 1000,
+                ..Memory::default()
+            },
+            loadavg: [1.0, 0.5, 0.25],
+        }
+    }
+
+    /// 用 Recorder 写出的记录文件
+    fn record(frames: &[(u64, Vec<Process>)]) -> String {
+        let header = Header {
+            version: VERSION,
+            interval_ms: 1000,
+            boot_ms: 0,
+            clk_tck: 100,
+            page_size: 4096,
+            mem_total: 1000,
+        };
+        let mut text = serde_json::to_string(&header).unwrap() + "\n";
+        let mut recorder = Recorder::new();
+        for (secs, processes) in frames {
+            for line in recorder.lines(from_millis(secs * 1000), &load(*secs), processes) {
+                text += &serde_json::to_string(&line).unwrap();
+                text += "\n";
+            }
+        }
+        text
+    }
+
+    fn recording() -> String {
+        record(&[
+            (10, vec![process(1, 0, 100), process(2, 0, 50)]),
+            (11, vec![process(1, 20, 300), process(2, 90, 50)]),
+            (12, vec![process(1, 100, 200)]),
+        ])
+    }
+
+    #[test]
+    fn round_trips_processes() {
+        let process = Process {
+            tty_nr: 0x8800,
+            nice: -5,
+            priority: 15,
+            exe: Some(PathBuf::from("/usr/bin/cc")),
+            cgroup: Some("/user.slice/user-1000.slice/session-2.scope".to_string()),
+            processor: Some(3),
+            ..process(7, 3, 9)
+        };
+        let text = record(&[(10, vec![process.clone()])]);
+        let mut reader = Reader::new(text.as_bytes()).unwrap();
+        let snapshot = reader.next_frame().unwrap().unwrap();
+        assert_eq!(snapshot.processes, vec![process]);
+        assert_eq!(snapshot.time, from_millis(10_000));
+        assert_eq!(reader.next_frame().unwrap(), None);
+    }
+
+    #[test]
+    fn writes_only_changes() {
+        let mut recorder = Recorder::new();
+        let first = recorder.lines(
+            UNIX_EPOCH,
+            &load(1),
+            &[process(1, 0, 100), process(2, 0, 50)],
+        );
+        assert_eq!(first.len(), 3);
+        // 1 没有变化，不再写出；2 退出；3 是新进程
+        let idle = process(1, 0, 100);
+        let lines = recorder.lines(UNIX_EPOCH, &load(2), &[idle, process(3, 5, 10)]);
+        let Line::Proc(info) = &lines[0] else {
+            panic!("应该先写出新进程的信息");
+        };
+        assert_eq!((info.id, info.pid), (2, 3));
+        let Some(Line::Frame(frame)) = lines.last() else {
+            panic!("最后一行应该是帧");
+        };
+        assert_eq!(frame.p.iter().map(Entry::id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(frame.exit, vec![1]);
+
+        // 进程号被复用时分配新的 id
+        let reused = Process {
+            start_time: from_millis(5_000),
+            ..process(3, 0, 10)
+        };
+        let lines = recorder.lines(UNIX_EPOCH, &load(3), &[process(1, 0, 100), reused]);
+        assert!(matches!(&lines[0], Line::Proc(info) if info.id == 3));
+
+        // 静态信息变化时重写
+        let renamed = Process {
+            cmdline: vec!["cc1".to_string()],
+            ..process(1, 0, 100)
+        };
+        let lines = recorder.lines(UNIX_EPOCH, &load(4), &[renamed]);
+        assert!(matches!(&lines[0], Line::Proc(info) if info.id == 0 && info.cmdline == ["cc1"]));
+    }
+
+    #[test]
+    fn reads_recordings() {
+        let text = recording();
+        let count = |text: &str| {
+            let mut reader = Reader::new(text.as_bytes())?;
+            let mut frames = 0;
+            while reader.next_frame()?.is_some() {
+                frames += 1;
+            }
+            io::Result::Ok(frames)
+        };
+        assert_eq!(count(&text).unwrap(), 3);
+
+        // 被中断的记录：最后一行不完整
+        let truncated = &text[..text.len() - 10];
+        assert_eq!(count(truncated).unwrap(), 2);
+
+        let broken = text.replacen("{\"frame\"", "{\"x\"", 1);
+        assert!(count(&broken).is_err());
+        assert!(Reader::new("".as_bytes()).is_err());
+        let old = text.replacen("\"version\":2", "\"version\":1", 1);
+        assert!(Reader::new(old.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn replays_frame_by_frame() {
+        let text = recording();
+        let replay = Replay::new(Reader::new(text.as_bytes()).unwrap()).unwrap();
+        let sys = replay.system().unwrap();
+        assert_eq!(sys.uptime, Duration::from_secs(10));
+        let users = Users::default();
+        let counts: Vec<usize> = (0..4)
+            .map(|_| replay.processes(&sys, &users).unwrap().len())
+            .collect();
+        assert_eq!(counts, vec![2, 2, 1, 1]);
+        assert_eq!(replay.now(), from_millis(12_000));
+        assert_eq!(replay.load().unwrap().cpus[0].busy, 600);
+        assert!(!replay.live());
+
+        let header = recording().lines().next().unwrap().to_string() + "\n";
+        assert!(Replay::new(Reader::new(header.as_bytes()).unwrap()).is_err());
+    }
+
+    #[test]
+    fn finds_peaks() {
+        let text = recording();
+        let summary = summarize(&mut Reader::new(text.as_bytes()).unwrap()).unwrap();
+        assert_eq!(summary.frames, 3);
+        assert_eq!(
+            summary.span,
+            Some((from_millis(10_000), from_millis(12_000)))
+        );
+        let peaks = summary.peaks;
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].pid, 2);
+        assert_eq!(peaks[0].cpu_percent, 90.0);
+        assert_eq!(peaks[0].frames, 2);
+        assert_eq!(peaks[1].pid, 1);
+        assert_eq!(peaks[1].cpu_percent, 80.0);
+        assert_eq!(peaks[1].rss_kb, 300);
+        assert_eq!(peaks[1].command, "cc -O2");
+
+        let lines = format_peaks(&peaks, true);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("PID USER %CPU       AT RSS       AT COMMAND"));
+    }
+}
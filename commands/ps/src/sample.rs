@@ -0,0 +1,145 @@
+//! %CPU 的两种算法
+//!
+//! 默认和 procps 一样，用进程整个生命周期的 CPU 时间除以运行时间；
+//! --sample 会读两次 /proc，用两次之间增加的 CPU 时间计算当前的占用率。
+
+use crate::process::Process;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// %CPU 的计算方式
+#[derive(Debug, Clone, PartialEq)]
+pub enum CpuMode {
+    /// 生命周期平均值
+    Lifetime,
+    /// 采样得到的占用率，按 [`Process::task_id`] 索引：-L、-T 时是线程号
+    Sampled {
+        interval: Duration,
+        percent: HashMap<i32, f64>,
+    },
+}
+
+impl CpuMode {
+    /// 表头中的说明，例如 %CPU(500ms)
+    pub fn label(&self) -> Option<String> {
+        match self {
+            CpuMode::Lifetime => None,
+            CpuMode::Sampled { interval, .. } => Some(format_duration(*interval)),
+        }
+    }
+}
+
+// --------------------------------------------------
+/// 根据两次快照计算每个进程（或线程）在 elapsed 时间内的 CPU 占用率。编号
+/// 相同但启动时间不同说明编号被复用了，和新进程一样只统计它自己的 CPU 时间
+pub fn sample(
+    before: &[Process],
+    after: &[Process],
+    elapsed: Duration,
+    clk_tck: u64,
+) -> HashMap<i32, f64> {
+    let before: HashMap<i32, &Process> = before.iter().map(|p| (p.task_id(), p)).collect();
+    let elapsed_ticks = elapsed.as_secs_f64() * clk_tck as f64;
+    after
+        .iter()
+        .map(|process| {
+            let ticks = process.utime + process.stime;
+            let previous = match before.get(&process.task_id()) {
+                Some(old) if old.start_time == process.start_time => old.utime + old.stime,
+                _ => 0,
+            };
+            let percent = if elapsed_ticks > 0.0 {
+                ticks.saturating_sub(previous) as f64 * 100.0 / elapsed_ticks
+            } else {
+                0.0
+            };
+            (process.task_id(), percent)
+        })
+        .collect()
+}
+
+// --------------------------------------------------
+/// 解析 500ms、1s、1.5s、2m 这样的时间，没有单位时按秒计算
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, scale) = if let Some(ms) = text.strip_suffix("ms") {
+        (ms, 0.001)
+    } else if let Some(s) = text.strip_suffix('s') {
+        (s, 1.0)
+    } else if let Some(m) = text.strip_suffix('m') {
+        (m, 60.0)
+    } else {
+        (text, 1.0)
+    };
+    let secs = number.parse::<f64>().ok()? * scale;
+    if secs.is_finite() && secs > 0.0 {
+        Some(Duration::from_secs_f64(secs))
+    } else {
+        None
+    }
+}
+
+// --------------------------------------------------
+pub fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_millis();
+    if ms.is_multiple_of(1000) {
+        format!("{}s", ms / 1000)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn process(pid: i32, ticks: u64, started: u64) -> Process {
+        Process {
+            utime: ticks,
+            start_time: SystemTime::UNIX_EPOCH + Duration::from_secs(started),
+            ..Process::fixture(pid, "test")
+        }
+    }
+
+    #[test]
+    fn samples_cpu_between_snapshots() {
+        let before = [process(1, 100, 0), process(2, 50, 0), process(3, 10, 0)];
+        // 1 用了 25 个滴答；2 没变；3 的进程号被复用；4 是新进程
+        let after = [
+            process(1, 125, 0),
+            process(2, 50, 0),
+            process(3, 5, 9),
+            process(4, 10, 9),
+        ];
+        let percent = sample(&before, &after, Duration::from_millis(500), 100);
+        assert_eq!(percent[&1], 50.0);
+        assert_eq!(percent[&2], 0.0);
+        assert_eq!(percent[&3], 10.0);
+        assert_eq!(percent[&4], 20.0);
+    }
+
+    #[test]
+    fn samples_threads_by_tid() {
+        let thread = |tid, ticks| Process {
+            tid: Some(tid),
+            ..process(7, ticks, 0)
+        };
+        let before = [thread(7, 0), thread(8, 100), thread(9, 100)];
+        let after = [thread(7, 0), thread(8, 150), thread(9, 110)];
+        let percent = sample(&before, &after, Duration::from_secs(1), 100);
+        assert_eq!(percent.len(), 3);
+        assert_eq!((percent[&7], percent[&8], percent[&9]), (0.0, 50.0, 10.0));
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1m"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("fast"), None);
+        assert_eq!(format_duration(Duration::from_millis(500)), "500ms");
+        assert_eq!(format_duration(Duration::from_secs(2)), "2s");
+    }
+}
@@ -0,0 +1,148 @@
+//! 进程筛选：-e、-u、-p、-C、-t、--ppid、--unit 以及 BSD 的 a、x
+
+use crate::process::Process;
+
+/// 运行 ps 的用户和终端，决定默认显示哪些进程
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Caller {
+    pub uid: u32,
+    pub tty_nr: u32,
+}
+
+/// 选择哪些进程
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// -e、-A 或 BSD 的 ax：所有进程
+    pub all: bool,
+    /// 使用了 BSD 语法
+    pub bsd: bool,
+    /// BSD 的 a：不限用户
+    pub any_user: bool,
+    /// BSD 的 x：包括没有终端的进程
+    pub no_tty: bool,
+    pub uids: Vec<u32>,
+    pub pids: Vec<i32>,
+    pub ppids: Vec<i32>,
+    pub commands: Vec<String>,
+    /// 终端名，例如 pts/0、tty1
+    pub ttys: Vec<String>,
+    /// systemd unit，例如 sshd.service，.service 后缀可以省略
+    pub units: Vec<String>,
+}
+
+impl Selection {
+    /// 是否给出了 -u、-p 等列表
+    fn has_lists(&self) -> bool {
+        !(self.uids.is_empty()
+            && self.pids.is_empty()
+            && self.ppids.is_empty()
+            && self.commands.is_empty()
+            && self.ttys.is_empty()
+            && self.units.is_empty())
+    }
+
+    // --------------------------------------------------
+    /// 每个列表对进程的判断，没有给出的列表为 None
+    fn criteria(&self, process: &Process) -> [Option<bool>; 6] {
+        [
+            (!self.uids.is_empty()).then(|| self.uids.contains(&process.uid)),
+            (!self.pids.is_empty()).then(|| self.pids.contains(&process.pid)),
+            (!self.ppids.is_empty()).then(|| self.ppids.contains(&process.ppid)),
+            (!self.commands.is_empty()).then(|| self.commands.contains(&process.name)),
+            (!self.ttys.is_empty())
+                .then(|| process.tty().is_some_and(|tty| self.ttys.contains(&tty))),
+            (!self.units.is_empty()).then(|| self.units.iter().any(|unit| in_unit(process, unit))),
+        ]
+    }
+
+    // --------------------------------------------------
+    /// 各个列表之间取并集。没有列表时，POSIX 语法默认显示当前用户在当前
+    /// 终端上的进程；BSD 语法默认显示当前用户有终端的进程，a 和 x 分别
+    /// 去掉这两个限制
+    pub fn matches(&self, process: &Process, caller: &Caller) -> bool {
+        if self.all {
+            return true;
+        }
+        if self.in_lists(process) {
+            return true;
+        }
+        if self.bsd {
+            if self.has_lists() && !self.any_user && !self.no_tty {
+                return false;
+            }
+            (self.any_user || process.uid == caller.uid) && (self.no_tty || process.tty_nr != 0)
+        } else {
+            !self.has_lists() && process.uid == caller.uid && process.tty_nr == caller.tty_nr
+        }
+    }
+
+    fn in_lists(&self, process: &Process) -> bool {
+        self.criteria(process).contains(&Some(true))
+    }
+
+    // --------------------------------------------------
+    /// pgrep 的语义：给出的每个列表都必须匹配，没有列表时匹配所有进程。
+    /// all、bsd 等 ps 的默认选择规则不参与
+    pub fn matches_every(&self, process: &Process) -> bool {
+        !self.criteria(process).contains(&Some(false))
+    }
+}
+
+// --------------------------------------------------
+/// 进程的 cgroup 路径中是否有名为 unit 的一层。和 systemctl status 一样，
+/// unit 下面的子 cgroup 也算，例如 user@1000.service 包括其中的所有 scope
+fn in_unit(process: &Process, unit: &str) -> bool {
+    let Some(path) = &process.cgroup else {
+        return false;
+    };
+    path.split('/').any(|component| {
+        component == unit
+            || component
+                .strip_suffix(".service")
+                .is_some_and(|name| name == unit)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_units() {
+        let caller = Caller { uid: 0, tty_nr: 0 };
+        let selection = Selection {
+            units: vec!["sshd".to_string(), "user@1000.service".to_string()],
+            ..Selection::default()
+        };
+        let in_cgroup = |path: &str| Process {
+            cgroup: Some(path.to_string()),
+            ..Process::fixture(10, "bash")
+        };
+        assert!(selection.matches(&in_cgroup("/system.slice/sshd.service"), &caller));
+        assert!(selection.matches(
+            &in_cgroup("/user.slice/user-1000.slice/user@1000.service/app.slice/a.scope"),
+            &caller
+        ));
+        assert!(!selection.matches(&in_cgroup("/system.slice/sshd-keygen.service"), &caller));
+        assert!(!selection.matches(&Process::fixture(11, "init"), &caller));
+    }
+
+    #[test]
+    fn unions_or_intersects_lists() {
+        let caller = Caller { uid: 0, tty_nr: 0 };
+        let selection = Selection {
+            uids: vec![1000],
+            ppids: vec![1],
+            ..Selection::default()
+        };
+        let process = |uid, ppid| Process {
+            uid,
+            ppid,
+            ..Process::fixture(10, "bash")
+        };
+        assert!(selection.matches(&process(1000, 7), &caller));
+        assert!(!selection.matches_every(&process(1000, 7)));
+        assert!(selection.matches_every(&process(1000, 1)));
+        assert!(Selection::default().matches_every(&process(0, 7)));
+    }
+}
@@ -0,0 +1,101 @@
+//! 信号名和编号，kill、pkill 和 --top 共用
+
+use std::io;
+
+/// Linux 的标准信号，按编号排列
+pub const SIGNALS: [(&str, libc::c_int); 31] = [
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("STKFLT", libc::SIGSTKFLT),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    ("PWR", libc::SIGPWR),
+    ("SYS", libc::SIGSYS),
+];
+
+/// 实时信号的最大编号
+const SIGRTMAX: libc::c_int = 64;
+
+// --------------------------------------------------
+/// 按编号（9）、名字（KILL）或带 SIG 前缀的名字（SIGKILL）查找信号，
+/// 名字不区分大小写。0 用来检查进程是否存在
+pub fn parse(text: &str) -> Option<libc::c_int> {
+    if let Ok(number) = text.parse::<libc::c_int>() {
+        return (0..=SIGRTMAX).contains(&number).then_some(number);
+    }
+    let upper = text.to_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, number)| *number)
+}
+
+// --------------------------------------------------
+/// 信号名，不带 SIG 前缀
+pub fn name(signal: libc::c_int) -> Option<&'static str> {
+    SIGNALS
+        .iter()
+        .find(|(_, number)| *number == signal)
+        .map(|(name, _)| *name)
+}
+
+// --------------------------------------------------
+/// 向进程发送信号，pid 为负数时发给整个进程组
+pub fn send(pid: i32, signal: libc::c_int) -> io::Result<()> {
+    // SAFETY: kill 只向指定的进程发送信号，没有内存安全方面的要求
+    if unsafe { libc::kill(pid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+// --------------------------------------------------
+/// kill -l 的输出：所有信号名，用空格分隔
+pub fn list() -> String {
+    let names: Vec<&str> = SIGNALS.iter().map(|(name, _)| *name).collect();
+    names.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_signals() {
+        assert_eq!(parse("9"), Some(libc::SIGKILL));
+        assert_eq!(parse("KILL"), Some(libc::SIGKILL));
+        assert_eq!(parse("sigterm"), Some(libc::SIGTERM));
+        assert_eq!(parse("0"), Some(0));
+        assert_eq!(parse("65"), None);
+        assert_eq!(parse("BOGUS"), None);
+        assert_eq!(name(libc::SIGHUP), Some("HUP"));
+        assert_eq!(name(40), None);
+        assert!(list().starts_with("HUP INT QUIT"));
+    }
+}
@@ -0,0 +1,330 @@
+//! 进程信息的来源
+//!
+//! ps 的其余部分只依赖 [`ProcessSource`]：
+//! - [`ProcFs`]：直接解析 /proc，Linux 上的默认实现
+//! - [`SysinfoSource`]：基于 sysinfo crate，需要打开 `sysinfo` feature。
+//!   它不能替代 /proc：TIME 和 %CPU 总是 0，见下文
+//! - [`MockSource`]：读取一棵固定的 /proc 目录树，时钟和系统参数都是
+//!   固定值，用于测试 ps 的完整输出
+
+use crate::process::Process;
+use crate::procfs::{self, Load, SystemInfo};
+use crate::users::Users;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// procfs、signal 和 top 直接使用 Linux 的 sysconf、kill 和信号编号，
+// sysinfo 后端也改变不了这一点
+#[cfg(not(target_os = "linux"))]
+compile_error!("ps 只支持 Linux");
+
+/// 一种读取进程和系统信息的方式
+pub trait ProcessSource {
+    /// 启动时间、时钟频率、内存总量等
+    fn system(&self) -> io::Result<SystemInfo>;
+
+    /// 所有进程，按进程号排列
+    fn processes(&self, sys: &SystemInfo, users: &Users) -> io::Result<Vec<Process>>;
+
+    /// 单个进程，不存在时返回 NotFound
+    fn process(&self, pid: i32, sys: &SystemInfo, users: &Users) -> io::Result<Process> {
+        self.processes(sys, users)?
+            .into_iter()
+            .find(|process| process.pid == pid)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("没有进程 {}", pid)))
+    }
+
+    /// --top 每次刷新时读取的系统负载
+    fn load(&self) -> io::Result<Load>;
+
+    /// 进程中除主线程外的线程，--pstree 使用。不支持时为空
+    fn threads(&self, _pid: i32) -> io::Result<Vec<(i32, String)>> {
+        Ok(vec![])
+    }
+
+    /// -L、-T：进程的每个线程一行。不支持时把进程本身当作唯一的线程
+    fn tasks(&self, process: &Process, _sys: &SystemInfo) -> io::Result<Vec<Process>> {
+        Ok(vec![Process {
+            tid: Some(process.pid),
+            ..process.clone()
+        }])
+    }
+
+    /// 运行 ps 的进程，用来决定默认显示哪些进程
+    fn current_pid(&self) -> i32 {
+        std::process::id() as i32
+    }
+
+    /// 计算 %CPU 和启动时间所用的当前时刻
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    /// --inspect 读取的 /proc 目录，没有 /proc 时为 None
+    fn proc_root(&self) -> Option<&Path> {
+        None
+    }
+
+    /// 读到的是正在运行的进程。回放记录时为 false，--top 不允许发送信号
+    fn live(&self) -> bool {
+        true
+    }
+}
+
+// --------------------------------------------------
+/// 默认的来源：解析 /proc
+pub fn default_source() -> Box<dyn ProcessSource> {
+    Box::new(ProcFs::new(crate::PROC))
+}
+
+/// 解析 /proc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcFs {
+    root: PathBuf,
+}
+
+impl ProcFs {
+    pub fn new(root: impl Into<PathBuf>) -> ProcFs {
+        ProcFs { root: root.into() }
+    }
+}
+
+impl ProcessSource for ProcFs {
+    fn system(&self) -> io::Result<SystemInfo> {
+        SystemInfo::read(&self.root)
+    }
+
+    fn processes(&self, sys: &SystemInfo, users: &Users) -> io::Result<Vec<Process>> {
+        procfs::processes(&self.root, sys, users)
+    }
+
+    fn process(&self, pid: i32, sys: &SystemInfo, users: &Users) -> io::Result<Process> {
+        procfs::read_process(&self.root, pid, sys, users)
+    }
+
+    fn load(&self) -> io::Result<Load> {
+        Load::read(&self.root)
+    }
+
+    fn threads(&self, pid: i32) -> io::Result<Vec<(i32, String)>> {
+        procfs::threads(&self.root, pid)
+    }
+
+    fn tasks(&self, process: &Process, sys: &SystemInfo) -> io::Result<Vec<Process>> {
+        procfs::tasks(&self.root, process, sys)
+    }
+
+    fn proc_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+/// 读取固定的 /proc 目录树。时钟滴答固定为 100，页大小固定为 4096，
+/// 当前时刻为 btime 加上 uptime，所以输出和运行测试的机器无关
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockSource {
+    procfs: ProcFs,
+    /// 假装运行 ps 的进程
+    pid: i32,
+}
+
+impl MockSource {
+    pub fn new(root: impl Into<PathBuf>, pid: i32) -> MockSource {
+        MockSource {
+            procfs: ProcFs::new(root),
+            pid,
+        }
+    }
+}
+
+impl ProcessSource for MockSource {
+    fn system(&self) -> io::Result<SystemInfo> {
+        Ok(SystemInfo {
+            clk_tck: 100,
+            page_size: 4096,
+            ..self.procfs.system()?
+        })
+    }
+
+    fn processes(&self, sys: &SystemInfo, users: &Users) -> io::Result<Vec<Process>> {
+        self.procfs.processes(sys, users)
+    }
+
+    fn process(&self, pid: i32, sys: &SystemInfo, users: &Users) -> io::Result<Process> {
+        self.procfs.process(pid, sys, users)
+    }
+
+    fn load(&self) -> io::Result<Load> {
+        self.procfs.load()
+    }
+
+    fn threads(&self, pid: i32) -> io::Result<Vec<(i32, String)>> {
+        self.procfs.threads(pid)
+    }
+
+    fn tasks(&self, process: &Process, sys: &SystemInfo) -> io::Result<Vec<Process>> {
+        self.procfs.tasks(process, sys)
+    }
+
+    fn current_pid(&self) -> i32 {
+        self.pid
+    }
+
+    fn now(&self) -> SystemTime {
+        self.system()
+            .map(|sys| sys.boot_time + sys.uptime)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+
+    fn proc_root(&self) -> Option<&Path> {
+        self.procfs.proc_root()
+    }
+}
+
+/// 基于 sysinfo crate 的来源，和 ps 的其余部分一样只在 Linux 上可用。
+/// sysinfo 不提供累计 CPU 时间、终端、线程数和 cgroup，这些字段为 0 或
+/// 空，TIME 和 %CPU 列因此总是 0
+#[cfg(feature = "sysinfo")]
+pub struct SysinfoSource {
+    system: std::cell::RefCell<sysinfo::System>,
+}
+
+#[cfg(feature = "sysinfo")]
+impl SysinfoSource {
+    pub fn new() -> SysinfoSource {
+        use sysinfo::SystemExt;
+        SysinfoSource {
+            system: std::cell::RefCell::new(sysinfo::System::new()),
+        }
+    }
+}
+
+#[cfg(feature = "sysinfo")]
+impl Default for SysinfoSource {
+    fn default() -> Self {
+        SysinfoSource::new()
+    }
+}
+
+#[cfg(feature = "sysinfo")]
+impl ProcessSource for SysinfoSource {
+    fn system(&self) -> io::Result<SystemInfo> {
+        use std::time::Duration;
+        use sysinfo::SystemExt;
+        let mut system = self.system.borrow_mut();
+        system.refresh_memory();
+        Ok(SystemInfo {
+            boot_time: SystemTime::UNIX_EPOCH + Duration::from_secs(system.boot_time()),
+            uptime: Duration::from_secs(system.uptime()),
+            clk_tck: 100,
+            page_size: 4096,
+            mem_total: system.total_memory() / 1024,
+        })
+    }
+
+    fn processes(&self, _sys: &SystemInfo, users: &Users) -> io::Result<Vec<Process>> {
+        use std::time::Duration;
+        use sysinfo::{PidExt, ProcessExt, ProcessStatus, SystemExt};
+        let mut system = self.system.borrow_mut();
+        system.refresh_processes();
+        let mut processes: Vec<Process> = system
+            .processes()
+            .values()
+            .map(|process| {
+                let pid = process.pid().as_u32() as i32;
+                let uid = process.user_id().map_or(0, |uid| **uid);
+                let code = match process.status() {
+                    ProcessStatus::Run => 'R',
+                    ProcessStatus::Sleep => 'S',
+                    // sysinfo 在 Linux 上用 Idle 表示不可中断的睡眠
+                    ProcessStatus::Idle => 'D',
+                    ProcessStatus::Stop => 'T',
+                    ProcessStatus::Tracing => 't',
+                    ProcessStatus::Zombie => 'Z',
+                    ProcessStatus::Dead => 'X',
+                    _ => '?',
+                };
+                let path =
+                    |path: &Path| Some(path.to_path_buf()).filter(|p| !p.as_os_str().is_empty());
+                Process {
+                    pid,
+                    ppid: process.parent().map_or(0, |pid| pid.as_u32() as i32),
+                    pgrp: pid,
+                    session: process.session_id().map_or(0, |pid| pid.as_u32() as i32),
+                    tty_nr: 0,
+                    tpgid: -1,
+                    name: process.name().to_string(),
+                    state: crate::process::State::from_code(code),
+                    uid,
+                    gid: process.group_id().map_or(0, |gid| *gid),
+                    user: users.name(uid),
+                    priority: 20,
+                    nice: 0,
+                    threads: 1,
+                    utime: 0,
+                    stime: 0,
+                    start_time: SystemTime::UNIX_EPOCH + Duration::from_secs(process.start_time()),
+                    vsz: process.virtual_memory() / 1024,
+                    rss: process.memory() / 1024,
+                    cmdline: process.cmd().to_vec(),
+                    environ: process.environ().to_vec(),
+                    cwd: path(process.cwd()),
+                    exe: path(process.exe()),
+                    cgroup: None,
+                    tid: None,
+                    processor: None,
+                }
+            })
+            .collect();
+        processes.sort_by_key(|process| process.pid);
+        Ok(processes)
+    }
+
+    fn load(&self) -> io::Result<Load> {
+        use sysinfo::SystemExt;
+        let mut system = self.system.borrow_mut();
+        system.refresh_memory();
+        let loadavg = system.load_average();
+        Ok(Load {
+            // sysinfo 只提供占用率，没有累计时间，不显示各核心
+            cpus: vec![],
+            memory: procfs::Memory {
+                total: system.total_memory() / 1024,
+                available: system.available_memory() / 1024,
+                swap_total: system.total_swap() / 1024,
+                swap_free: system.free_swap() / 1024,
+            },
+            loadavg: [loadavg.one, loadavg.five, loadavg.fifteen],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_own_process() {
+        let source = ProcFs::new(crate::PROC);
+        let sys = source.system().unwrap();
+        let me = source
+            .process(source.current_pid(), &sys, &Users::default())
+            .unwrap();
+        assert_eq!(me.pid, std::process::id() as i32);
+        assert_eq!(source.proc_root(), Some(Path::new("/proc")));
+    }
+
+    #[cfg(feature = "sysinfo")]
+    #[test]
+    fn sysinfo_sees_own_process() {
+        let source = SysinfoSource::new();
+        let sys = source.system().unwrap();
+        assert!(sys.mem_total > 0);
+        let me = source
+            .process(source.current_pid(), &sys, &Users::default())
+            .unwrap();
+        assert!(me.rss > 0);
+        assert_eq!(source.proc_root(), None);
+    }
+}
@@ -0,0 +1,672 @@
+//! --top：类似 top 的交互界面
+//!
+//! 每次刷新重新读取进程信息，%CPU 和每个核心的占用率都是两次刷新之间的
+//! 采样值。第一帧还没有上一次的数据，%CPU 使用生命周期平均值。
+
+use crate::error::PsError;
+use crate::format::{self, Column, Context, Field, SortKey};
+use crate::process::Process;
+use crate::procfs::{CpuTimes, Load, SystemInfo};
+use crate::sample::{self, CpuMode};
+use crate::signal;
+use crate::source::ProcessSource;
+use crate::users::Users;
+use chrono::{DateTime, Local};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// 发送信号的按键，按下后还要按 y 确认
+const SIGNAL_KEYS: [(char, libc::c_int); 6] = [
+    ('k', libc::SIGTERM),
+    ('K', libc::SIGKILL),
+    ('i', libc::SIGINT),
+    ('h', libc::SIGHUP),
+    ('s', libc::SIGSTOP),
+    ('c', libc::SIGCONT),
+];
+
+const HELP: &str = "q 退出  ↑↓ 选择  < > 排序列  r 反向  P/M/N/T 按 CPU/内存/PID/时间  \
+                    / 搜索  k TERM  K KILL  i INT  h HUP  s STOP  c CONT（按 y 确认）";
+
+/// 占用率条的宽度，包括两边的括号
+const BAR_WIDTH: usize = 17;
+
+/// 每个核心一格的宽度：名字、占用率条和百分比
+const CORE_WIDTH: usize = 5 + BAR_WIDTH + 7;
+
+// --------------------------------------------------
+/// --top 默认显示的列
+pub fn default_fields() -> Vec<Field> {
+    vec![
+        Field::new(Column::Pid),
+        Field::new(Column::User),
+        Field::new(Column::Cpu),
+        Field::new(Column::Mem),
+        Field::new(Column::Vsz),
+        Field::new(Column::Rss),
+        Field::new(Column::Stat),
+        Field::new(Column::Time),
+        Field::new(Column::Args),
+    ]
+}
+
+/// 一次刷新读到的数据
+#[derive(Clone)]
+struct Snapshot {
+    processes: Vec<Process>,
+    ctx: Context,
+    /// 每个 CPU 核心的占用率
+    cores: Vec<f64>,
+    load: Load,
+}
+
+/// 按键的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Quit,
+    Redraw,
+    /// 确认后向进程发送信号
+    Signal(i32, libc::c_int),
+    Ignore,
+}
+
+/// 界面状态
+struct Top {
+    fields: Vec<Field>,
+    sort: SortKey,
+    /// 按命令行过滤，不区分大小写
+    filter: String,
+    /// 正在输入搜索条件
+    searching: bool,
+    /// 选中的进程号，刷新后按进程号找回选中的行
+    selected: Option<i32>,
+    /// 表格第一行对应的下标
+    scroll: usize,
+    /// 表格能显示的行数，绘制时更新
+    page: usize,
+    /// 状态栏上的提示，下次按键时清除
+    message: Option<String>,
+    /// 等待确认的信号：(进程号, 进程名, 信号)
+    pending: Option<(i32, String, libc::c_int)>,
+    /// 读到的是正在运行的进程，回放记录时不能发送信号
+    live: bool,
+}
+
+impl Top {
+    fn new(fields: Vec<Field>) -> Top {
+        Top {
+            fields,
+            sort: SortKey {
+                column: Column::Cpu,
+                descending: true,
+            },
+            filter: String::new(),
+            searching: false,
+            selected: None,
+            scroll: 0,
+            page: 1,
+            message: None,
+            pending: None,
+            live: true,
+        }
+    }
+
+    // --------------------------------------------------
+    /// 过滤并排序后要显示的进程
+    fn visible(&self, snapshot: &Snapshot) -> Vec<Process> {
+        let filter = self.filter.to_lowercase();
+        let mut processes: Vec<Process> = snapshot
+            .processes
+            .iter()
+            .filter(|p| filter.is_empty() || p.command().to_lowercase().contains(&filter))
+            .cloned()
+            .collect();
+        format::sort(&mut processes, &[self.sort], &snapshot.ctx);
+        processes
+    }
+
+    /// 选中行的下标，选中的进程已经不在列表中时为第一行
+    fn position(&self, visible: &[Process]) -> usize {
+        self.selected
+            .and_then(|pid| visible.iter().position(|p| p.pid == pid))
+            .unwrap_or(0)
+    }
+
+    /// 用户选中并且仍在列表中的进程。信号只发给它，不会落到默认的第一行
+    fn chosen<'a>(&self, visible: &'a [Process]) -> Option<&'a Process> {
+        let pid = self.selected?;
+        visible.iter().find(|p| p.pid == pid)
+    }
+
+    fn select(&mut self, visible: &[Process], index: usize) {
+        let index = index.min(visible.len().saturating_sub(1));
+        self.selected = visible.get(index).map(|p| p.pid);
+    }
+
+    // --------------------------------------------------
+    fn key(&mut self, key: KeyEvent, visible: &[Process]) -> Action {
+        self.message = None;
+        // 原始模式下 Ctrl-C 不产生 SIGINT，搜索和确认时也要能退出
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+        if let Some((pid, name, signal)) = self.pending.take() {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return Action::Signal(pid, signal);
+            }
+            let signal = signal::name(signal).unwrap_or("?");
+            self.message = Some(format!("已取消向 {} ({}) 发送 SIG{}", pid, name, signal));
+            return Action::Redraw;
+        }
+        if self.searching {
+            match key.code {
+                KeyCode::Char(c) => self.filter.push(c),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.searching = false;
+                }
+                _ => return Action::Ignore,
+            }
+            return Action::Redraw;
+        }
+
+        let position = self.position(visible);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Up => self.select(visible, position.saturating_sub(1)),
+            KeyCode::Down => self.select(visible, position + 1),
+            KeyCode::PageUp => self.select(visible, position.saturating_sub(self.page)),
+            KeyCode::PageDown => self.select(visible, position + self.page),
+            KeyCode::Home => self.select(visible, 0),
+            KeyCode::End => self.select(visible, visible.len()),
+            KeyCode::Char('<') => self.cycle_sort(false),
+            KeyCode::Char('>') => self.cycle_sort(true),
+            KeyCode::Char('r') => self.sort.descending = !self.sort.descending,
+            KeyCode::Char('P') => self.sort_by(Column::Cpu, true),
+            KeyCode::Char('M') => self.sort_by(Column::Mem, true),
+            KeyCode::Char('N') => self.sort_by(Column::Pid, false),
+            KeyCode::Char('T') => self.sort_by(Column::Time, true),
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Char(c) => match SIGNAL_KEYS.iter().find(|(key, _)| *key == c) {
+                Some(&(_, signal)) => self.confirm(signal, visible),
+                None => return Action::Ignore,
+            },
+            _ => return Action::Ignore,
+        }
+        Action::Redraw
+    }
+
+    fn sort_by(&mut self, column: Column, descending: bool) {
+        self.sort = SortKey { column, descending };
+    }
+
+    /// 在显示的列之间切换排序列
+    fn cycle_sort(&mut self, forward: bool) {
+        let count = self.fields.len();
+        let current = self
+            .fields
+            .iter()
+            .position(|f| f.column == self.sort.column);
+        let next = match (current, forward) {
+            (None, _) => 0,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+        };
+        self.sort.column = self.fields[next].column;
+    }
+
+    // --------------------------------------------------
+    /// 按下信号键：没有选中进程时拒绝，否则等待确认
+    fn confirm(&mut self, signal: libc::c_int, visible: &[Process]) {
+        if !self.live {
+            self.message = Some("回放记录时不能发送信号".to_string());
+            return;
+        }
+        match self.chosen(visible) {
+            Some(process) => self.pending = Some((process.pid, process.name.clone(), signal)),
+            None => self.message = Some("请先用 ↑↓ 选择进程".to_string()),
+        }
+    }
+
+    // --------------------------------------------------
+    fn signal(&mut self, pid: i32, signal: libc::c_int) {
+        let name = signal::name(signal).unwrap_or("?");
+        self.message = Some(match signal::send(pid, signal) {
+            Ok(()) => format!("已向 {} 发送 SIG{}", pid, name),
+            Err(e) => format!("无法向 {} 发送 SIG{}: {}", pid, name, e),
+        });
+    }
+
+    // --------------------------------------------------
+    fn status(&self) -> String {
+        if self.searching {
+            return format!("搜索: {}_", self.filter);
+        }
+        if let Some((pid, name, signal)) = &self.pending {
+            let signal = signal::name(*signal).unwrap_or("?");
+            return format!(
+                "向 {} ({}) 发送 SIG{}？按 y 确认，其他键取消",
+                pid, name, signal
+            );
+        }
+        if let Some(message) = &self.message {
+            return message.clone();
+        }
+        let order = if self.sort.descending { "-" } else { "+" };
+        let mut status = format!("排序: {}{}", order, self.sort.column.header());
+        if !self.filter.is_empty() {
+            status.push_str(&format!("  过滤: {}", self.filter));
+        }
+        format!("{}  {}", status, HELP)
+    }
+
+    // --------------------------------------------------
+    fn draw(&mut self, out: &mut impl Write, snapshot: &Snapshot) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let (width, height) = (cols as usize, rows as usize);
+
+        let mut lines = summary(snapshot, width);
+        lines.push(self.status());
+        let visible = self.visible(snapshot);
+        let mut fields = self.fields.clone();
+        snapshot.ctx.label_headers(&mut fields);
+        let table = format::format_table(&fields, &visible, &snapshot.ctx, true);
+
+        // 表头占一行，剩下的给进程
+        self.page = height.saturating_sub(lines.len() + 1).max(1);
+        let position = self.position(&visible);
+        let chosen = self.chosen(&visible).is_some();
+        if position < self.scroll {
+            self.scroll = position;
+        } else if position >= self.scroll + self.page {
+            self.scroll = position + 1 - self.page;
+        }
+        self.scroll = self.scroll.min(visible.len().saturating_sub(self.page));
+
+        queue!(out, cursor::MoveTo(0, 0))?;
+        for line in &lines {
+            print_line(out, line, width, false)?;
+        }
+        if let Some((header, rows)) = table.split_first() {
+            print_line(out, header, width, true)?;
+            for (i, row) in rows.iter().enumerate().skip(self.scroll).take(self.page) {
+                print_line(out, row, width, chosen && i == position)?;
+            }
+        }
+        queue!(out, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+        out.flush()
+    }
+}
+
+// --------------------------------------------------
+/// 打印一行，超出终端宽度的部分截掉
+fn print_line(out: &mut impl Write, line: &str, width: usize, reverse: bool) -> io::Result<()> {
+    let line: String = line.chars().take(width).collect();
+    if reverse {
+        // 反显整行，包括行尾的空白
+        let pad = " ".repeat(width.saturating_sub(line.chars().count()));
+        queue!(
+            out,
+            SetAttribute(Attribute::Reverse),
+            Print(line),
+            Print(pad),
+            SetAttribute(Attribute::Reset)
+        )?;
+    } else {
+        queue!(
+            out,
+            Print(line),
+            terminal::Clear(terminal::ClearType::UntilNewLine)
+        )?;
+    }
+    queue!(out, cursor::MoveToNextLine(1))
+}
+
+// --------------------------------------------------
+/// 界面顶部的系统概况：时间、运行时间、负载、每个核心、内存和交换区
+fn summary(snapshot: &Snapshot, width: usize) -> Vec<String> {
+    let ctx = &snapshot.ctx;
+    let now: DateTime<Local> = ctx.now.into();
+    let up = ctx
+        .now
+        .duration_since(ctx.sys.boot_time)
+        .unwrap_or_default()
+        .as_secs();
+    let [one, five, fifteen] = snapshot.load.loadavg;
+    let mut lines = vec![format!(
+        "ps --top - {} up {}, load average: {:.2} {:.2} {:.2}, {} processes",
+        now.format("%H:%M:%S"),
+        format_uptime(up),
+        one,
+        five,
+        fifteen,
+        snapshot.processes.len()
+    )];
+
+    let per_line = (width / (CORE_WIDTH + 2)).max(1);
+    let cells: Vec<String> = snapshot
+        .cores
+        .iter()
+        .enumerate()
+        .map(|(i, usage)| {
+            let name = format!("cpu{}", i);
+            format!("{:<5}{} {:>5.1}%", name, bar(*usage, BAR_WIDTH), usage)
+        })
+        .collect();
+    for chunk in cells.chunks(per_line) {
+        lines.push(chunk.join("  "));
+    }
+
+    let memory = &snapshot.load.memory;
+    let used = memory.total.saturating_sub(memory.available);
+    let swap_used = memory.swap_total.saturating_sub(memory.swap_free);
+    lines.push(memory_line("Mem", used, memory.total));
+    lines.push(memory_line("Swap", swap_used, memory.swap_total));
+    lines
+}
+
+// --------------------------------------------------
+fn memory_line(name: &str, used: u64, total: u64) -> String {
+    let percent = used as f64 * 100.0 / total.max(1) as f64;
+    format!(
+        "{:<5}{} {}/{} MiB",
+        name,
+        bar(percent, BAR_WIDTH),
+        used / 1024,
+        total / 1024
+    )
+}
+
+// --------------------------------------------------
+/// [|||||     ] 形式的占用率条，width 包括两边的括号
+fn bar(percent: f64, width: usize) -> String {
+    let inner = width.saturating_sub(2);
+    let filled = ((percent / 100.0 * inner as f64).round() as usize).min(inner);
+    format!("[{}{}]", "|".repeat(filled), " ".repeat(inner - filled))
+}
+
+// --------------------------------------------------
+/// 和 top 一样：不到一天显示 时:分，否则加上天数
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    match days {
+        0 => format!("{}:{:02}", hours, minutes),
+        1 => format!("1 day, {}:{:02}", hours, minutes),
+        _ => format!("{} days, {}:{:02}", days, hours, minutes),
+    }
+}
+
+// --------------------------------------------------
+/// 读取一次数据。有上一次的数据时 %CPU 和核心占用率按两次之间的差计算
+fn read(
+    source: &dyn ProcessSource,
+    sys: SystemInfo,
+    users: &Users,
+    interval: Duration,
+    previous: Option<(&Snapshot, Instant)>,
+) -> io::Result<Snapshot> {
+    let processes = source.processes(&sys, users)?;
+    let load = source.load()?;
+    let now = source.now();
+    // 回放到最后一帧后停在那里，不再和自己比较
+    if let Some((before, _)) = previous {
+        if !source.live() && now == before.ctx.now {
+            return Ok(before.clone());
+        }
+    }
+    let (cpu, cores) = match previous {
+        None => (
+            CpuMode::Lifetime,
+            load.cpus
+                .iter()
+                .map(|cpu| cpu.usage_since(&CpuTimes::default()))
+                .collect(),
+        ),
+        Some((before, taken)) => {
+            // 回放记录时两帧之间的时间来自记录，而不是实际经过的时间。
+            // 表头按 10ms 取整显示记录的间隔
+            let (elapsed, interval) = if source.live() {
+                (taken.elapsed(), interval)
+            } else {
+                let elapsed = now.duration_since(before.ctx.now).unwrap_or_default();
+                let rounded = (elapsed.as_millis() as u64 + 5) / 10 * 10;
+                (elapsed, Duration::from_millis(rounded))
+            };
+            let percent = sample::sample(&before.processes, &processes, elapsed, sys.clk_tck);
+            let cores = load
+                .cpus
+                .iter()
+                .zip(&before.load.cpus)
+                .map(|(now, earlier)| now.usage_since(earlier))
+                .collect();
+            (CpuMode::Sampled { interval, percent }, cores)
+        }
+    };
+    Ok(Snapshot {
+        processes,
+        ctx: Context {
+            sys,
+            now,
+            cpu,
+            forest: None,
+        },
+        cores,
+        load,
+    })
+}
+
+/// 进入备用屏幕和原始模式，离开时（包括出错返回时）恢复终端
+struct Screen;
+
+impl Screen {
+    fn enter(out: &mut impl Write) -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// --------------------------------------------------
+/// 运行交互界面，每隔 interval 刷新一次，直到按下 q
+pub fn run(
+    source: &dyn ProcessSource,
+    sys: SystemInfo,
+    users: &Users,
+    fields: Vec<Field>,
+    interval: Duration,
+) -> Result<(), PsError> {
+    let mut top = Top::new(fields);
+    top.live = source.live();
+    let mut snapshot = read(source, sys, users, interval, None)?;
+    let mut taken = Instant::now();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let _screen = Screen::enter(&mut out)?;
+    loop {
+        top.draw(&mut out, &snapshot)?;
+        let deadline = taken + interval;
+        loop {
+            let now = Instant::now();
+            if now >= deadline || !event::poll(deadline - now)? {
+                break;
+            }
+            match event::read()? {
+                Event::Key(key) => {
+                    let visible = top.visible(&snapshot);
+                    match top.key(key, &visible) {
+                        Action::Quit => return Ok(()),
+                        Action::Signal(pid, signal) => top.signal(pid, signal),
+                        Action::Redraw => {}
+                        Action::Ignore => continue,
+                    }
+                }
+                Event::Resize(..) => {}
+                _ => continue,
+            }
+            top.draw(&mut out, &snapshot)?;
+        }
+        snapshot = read(source, sys, users, interval, Some((&snapshot, taken)))?;
+        taken = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procfs::Memory;
+    use std::time::UNIX_EPOCH;
+
+    fn snapshot() -> Snapshot {
+        let process = |pid, name: &str, rss| Process {
+            rss,
+            cmdline: vec![format!("/usr/bin/{}", name)],
+            ..Process::fixture(pid, name)
+        };
+        Snapshot {
+            processes: vec![
+                process(1, "init", 100),
+                process(2, "sshd", 300),
+                process(3, "bash", 200),
+            ],
+            ctx: Context {
+                sys: SystemInfo {
+                    boot_time: UNIX_EPOCH,
+                    uptime: Duration::from_secs(90000),
+                    clk_tck: 100,
+                    page_size: 4096,
+                    mem_total: 1000,
+                },
+                now: UNIX_EPOCH + Duration::from_secs(90000),
+                cpu: CpuMode::Lifetime,
+                forest: None,
+            },
+            cores: vec![50.0, 0.0],
+            load: Load {
+                cpus: vec![],
+                memory: Memory {
+                    total: 4096,
+                    available: 1024,
+                    swap_total: 0,
+                    swap_free: 0,
+                },
+                loadavg: [0.5, 0.25, 0.0],
+            },
+        }
+    }
+
+    fn press(top: &mut Top, snapshot: &Snapshot, code: KeyCode) -> Action {
+        let visible = top.visible(snapshot);
+        top.key(KeyEvent::new(code, KeyModifiers::NONE), &visible)
+    }
+
+    fn pids(top: &Top, snapshot: &Snapshot) -> Vec<i32> {
+        top.visible(snapshot).iter().map(|p| p.pid).collect()
+    }
+
+    #[test]
+    fn sorts_and_filters() {
+        let snapshot = snapshot();
+        let mut top = Top::new(default_fields());
+        press(&mut top, &snapshot, KeyCode::Char('M'));
+        assert_eq!(pids(&top, &snapshot), vec![2, 3, 1]);
+        press(&mut top, &snapshot, KeyCode::Char('r'));
+        assert_eq!(pids(&top, &snapshot), vec![1, 3, 2]);
+        // %MEM 右边是 VSZ
+        press(&mut top, &snapshot, KeyCode::Char('>'));
+        assert_eq!(top.sort.column, Column::Vsz);
+
+        press(&mut top, &snapshot, KeyCode::Char('/'));
+        for c in "SSH".chars() {
+            press(&mut top, &snapshot, KeyCode::Char(c));
+        }
+        assert_eq!(press(&mut top, &snapshot, KeyCode::Enter), Action::Redraw);
+        assert_eq!(pids(&top, &snapshot), vec![2]);
+        assert_eq!(press(&mut top, &snapshot, KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn moves_selection_and_sends_signals() {
+        let snapshot = snapshot();
+        let mut top = Top::new(default_fields());
+        press(&mut top, &snapshot, KeyCode::Char('N'));
+        press(&mut top, &snapshot, KeyCode::Down);
+        press(&mut top, &snapshot, KeyCode::Down);
+        press(&mut top, &snapshot, KeyCode::Down);
+        assert_eq!(top.selected, Some(3));
+        press(&mut top, &snapshot, KeyCode::Home);
+        assert_eq!(top.selected, Some(1));
+        assert_eq!(
+            press(&mut top, &snapshot, KeyCode::Char('K')),
+            Action::Redraw
+        );
+        assert!(top.status().starts_with("向 1 (init) 发送 SIGKILL？"));
+        assert_eq!(
+            press(&mut top, &snapshot, KeyCode::Char('y')),
+            Action::Signal(1, libc::SIGKILL)
+        );
+        // 其他键取消
+        press(&mut top, &snapshot, KeyCode::Char('h'));
+        assert_eq!(
+            press(&mut top, &snapshot, KeyCode::Char('n')),
+            Action::Redraw
+        );
+        assert!(top.pending.is_none());
+        assert!(top.status().starts_with("已取消"));
+    }
+
+    #[test]
+    fn refuses_signals_without_selection() {
+        let snapshot = snapshot();
+        let mut top = Top::new(default_fields());
+        assert_eq!(
+            press(&mut top, &snapshot, KeyCode::Char('k')),
+            Action::Redraw
+        );
+        assert!(top.pending.is_none());
+        assert_eq!(top.status(), "请先用 ↑↓ 选择进程");
+
+        top.selected = Some(2);
+        top.live = false;
+        press(&mut top, &snapshot, KeyCode::Char('k'));
+        assert!(top.pending.is_none());
+    }
+
+    #[test]
+    fn quits_while_searching() {
+        let snapshot = snapshot();
+        let mut top = Top::new(default_fields());
+        press(&mut top, &snapshot, KeyCode::Char('/'));
+        let visible = top.visible(&snapshot);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(top.key(ctrl_c, &visible), Action::Quit);
+        assert_eq!(top.filter, "");
+    }
+
+    #[test]
+    fn summarizes_system() {
+        let lines = summary(&snapshot(), 80);
+        assert!(lines[0].ends_with("up 1 day, 1:00, load average: 0.50 0.25 0.00, 3 processes"));
+        assert_eq!(
+            lines[1],
+            "cpu0 [||||||||       ]  50.0%  cpu1 [               ]   0.0%"
+        );
+        assert_eq!(lines[2], "Mem  [|||||||||||    ] 3/4 MiB");
+        assert_eq!(lines[3], "Swap [               ] 0/0 MiB");
+        assert_eq!(bar(150.0, 6), "[||||]");
+    }
+}
@@ -0,0 +1,296 @@
+//! 进程树：--forest 的缩进和 --pstree 的树形图
+
+use crate::process::Process;
+use std::collections::{HashMap, HashSet};
+
+/// 按父子关系组织的进程，子进程保持传入时的顺序
+pub struct Tree<'a> {
+    roots: Vec<&'a Process>,
+    children: HashMap<i32, Vec<&'a Process>>,
+}
+
+impl<'a> Tree<'a> {
+    /// 父进程不在列表中的进程作为树根
+    pub fn new(processes: &'a [Process]) -> Tree<'a> {
+        let pids: HashSet<i32> = processes.iter().map(|p| p.pid).collect();
+        let mut roots = vec![];
+        let mut children: HashMap<i32, Vec<&Process>> = HashMap::new();
+        for process in processes {
+            if process.ppid != process.pid && pids.contains(&process.ppid) {
+                children.entry(process.ppid).or_default().push(process);
+            } else {
+                roots.push(process);
+            }
+        }
+        Tree { roots, children }
+    }
+
+    fn children(&self, pid: i32) -> &[&'a Process] {
+        self.children.get(&pid).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // --------------------------------------------------
+    /// 深度优先的顺序和每个进程的深度，树根的深度为 0
+    pub fn forest(&self) -> Vec<(&'a Process, usize)> {
+        let mut order = vec![];
+        let mut stack: Vec<(&Process, usize)> = self.roots.iter().rev().map(|&p| (p, 0)).collect();
+        while let Some((process, depth)) = stack.pop() {
+            order.push((process, depth));
+            for &child in self.children(process.pid).iter().rev() {
+                stack.push((child, depth + 1));
+            }
+        }
+        order
+    }
+}
+
+// --------------------------------------------------
+/// --forest：按树的顺序排列进程，返回排好的进程和每个进程号的深度
+pub fn forest(processes: &[Process]) -> (Vec<Process>, HashMap<i32, usize>) {
+    let tree = Tree::new(processes);
+    let order = tree.forest();
+    let depth = order.iter().map(|(p, depth)| (p.pid, *depth)).collect();
+    let processes = order.into_iter().map(|(p, _)| p.clone()).collect();
+    (processes, depth)
+}
+
+// --------------------------------------------------
+/// --forest 中命令前的缩进，和 procps 相同
+pub fn forest_prefix(depth: usize) -> String {
+    if depth == 0 {
+        String::new()
+    } else {
+        format!("{} \\_ ", "    ".repeat(depth - 1))
+    }
+}
+
+/// --pstree 的选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PstreeOptions {
+    /// 只显示以这个进程为根的子树
+    pub root: Option<i32>,
+    /// 在名字后显示进程号，此时不合并相同的子树
+    pub pids: bool,
+    /// 加粗显示这个进程及其所有祖先
+    pub highlight: Option<i32>,
+    /// 标准输出是终端，可以加粗。否则 highlight 不起作用
+    pub bold: bool,
+}
+
+/// 树中的一个节点：进程或线程
+struct Node {
+    /// 名字，显示进程号时包括进程号
+    text: String,
+    bold: bool,
+    children: Vec<Node>,
+}
+
+// --------------------------------------------------
+/// pstree 风格的树形图。threads 是每个进程除主线程外的线程 (tid, 名字)，
+/// 显示为 {名字}；名字相同的兄弟节点合并成 N*[名字]
+pub fn pstree(
+    processes: &[Process],
+    threads: &HashMap<i32, Vec<(i32, String)>>,
+    options: &PstreeOptions,
+) -> Vec<String> {
+    let tree = Tree::new(processes);
+    let highlight = options
+        .highlight
+        .filter(|_| options.bold)
+        .map(|pid| ancestors(processes, pid))
+        .unwrap_or_default();
+    let roots: Vec<&Process> = match options.root {
+        Some(pid) => processes.iter().filter(|p| p.pid == pid).collect(),
+        None => tree.roots.clone(),
+    };
+    roots
+        .into_iter()
+        .flat_map(|root| render(&node(&tree, root, threads, options, &highlight)))
+        .collect()
+}
+
+// --------------------------------------------------
+/// 进程本身和它的所有祖先
+pub fn ancestors(processes: &[Process], pid: i32) -> HashSet<i32> {
+    let parents: HashMap<i32, i32> = processes.iter().map(|p| (p.pid, p.ppid)).collect();
+    let mut found = HashSet::new();
+    let mut pid = pid;
+    while parents.contains_key(&pid) && found.insert(pid) {
+        pid = parents[&pid];
+    }
+    found
+}
+
+// --------------------------------------------------
+fn node(
+    tree: &Tree,
+    process: &Process,
+    threads: &HashMap<i32, Vec<(i32, String)>>,
+    options: &PstreeOptions,
+    highlight: &HashSet<i32>,
+) -> Node {
+    let label = |name: &str, pid: i32| {
+        if options.pids {
+            format!("{}({})", name, pid)
+        } else {
+            name.to_string()
+        }
+    };
+    let mut children: Vec<&Process> = tree.children(process.pid).to_vec();
+    children.sort_by(|a, b| a.name.cmp(&b.name).then(a.pid.cmp(&b.pid)));
+    let mut nodes: Vec<Node> = children
+        .into_iter()
+        .map(|child| node(tree, child, threads, options, highlight))
+        .collect();
+    for (tid, name) in threads.get(&process.pid).into_iter().flatten() {
+        nodes.push(Node {
+            text: label(&format!("{{{}}}", name), *tid),
+            bold: false,
+            children: vec![],
+        });
+    }
+    Node {
+        text: label(&process.name, process.pid),
+        bold: highlight.contains(&process.pid),
+        children: nodes,
+    }
+}
+
+// --------------------------------------------------
+/// 把节点画成若干行。只有一个子节点时接在同一行，多个子节点时用
+/// ┬ ├ └ 分叉
+fn render(node: &Node) -> Vec<String> {
+    let label = if node.bold {
+        format!("\x1b[1m{}\x1b[0m", node.text)
+    } else {
+        node.text.clone()
+    };
+    let children = merge(node.children.iter().map(render).collect());
+    // 缩进按显示宽度计算，不包括加粗用的控制字符
+    let indent = " ".repeat(node.text.chars().count());
+
+    let mut lines = vec![];
+    let last = children.len().saturating_sub(1);
+    for (k, child) in children.iter().enumerate() {
+        for (i, line) in child.iter().enumerate() {
+            let prefix = match (children.len(), k, i) {
+                (1, _, 0) => format!("{}───", label),
+                (1, _, _) => format!("{}   ", indent),
+                (_, 0, 0) => format!("{}─┬─", label),
+                (_, k, 0) if k == last => format!("{} └─", indent),
+                (_, _, 0) => format!("{} ├─", indent),
+                (_, k, _) if k == last => format!("{}   ", indent),
+                _ => format!("{} │ ", indent),
+            };
+            lines.push(prefix + line);
+        }
+    }
+    if lines.is_empty() {
+        lines.push(label);
+    }
+    lines
+}
+
+// --------------------------------------------------
+/// 合并内容相同的单行兄弟节点，例如 4 个 agetty 显示为 4*[agetty]
+fn merge(children: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let mut merged: Vec<(Vec<String>, usize)> = vec![];
+    for child in children {
+        match merged
+            .iter_mut()
+            .find(|(lines, _)| child.len() == 1 && *lines == child)
+        {
+            Some((_, count)) => *count += 1,
+            None => merged.push((child, 1)),
+        }
+    }
+    merged
+        .into_iter()
+        .map(|(lines, count)| {
+            if count > 1 {
+                vec![format!("{}*[{}]", count, lines[0])]
+            } else {
+                lines
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, ppid: i32, name: &str) -> Process {
+        Process {
+            ppid,
+            ..Process::fixture(pid, name)
+        }
+    }
+
+    fn processes() -> Vec<Process> {
+        vec![
+            process(1, 0, "init"),
+            process(2, 1, "sshd"),
+            process(3, 2, "bash"),
+            process(4, 1, "getty"),
+            process(5, 1, "getty"),
+            process(6, 3, "vim"),
+            process(7, 0, "kthreadd"),
+        ]
+    }
+
+    #[test]
+    fn orders_forest_depth_first() {
+        let (ordered, depth) = forest(&processes());
+        let pids: Vec<i32> = ordered.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1, 2, 3, 6, 4, 5, 7]);
+        assert_eq!(depth[&6], 3);
+        assert_eq!(depth[&7], 0);
+        assert_eq!(forest_prefix(0), "");
+        assert_eq!(forest_prefix(2), "     \\_ ");
+    }
+
+    #[test]
+    fn draws_pstree() {
+        let mut threads = HashMap::new();
+        threads.insert(
+            3,
+            vec![(8, "worker".to_string()), (9, "worker".to_string())],
+        );
+        let lines = pstree(&processes(), &threads, &PstreeOptions::default());
+        assert_eq!(
+            lines,
+            vec![
+                "init─┬─2*[getty]",
+                "     └─sshd───bash─┬─vim",
+                "                   └─2*[{worker}]",
+                "kthreadd",
+            ]
+        );
+
+        let options = PstreeOptions {
+            root: Some(2),
+            pids: true,
+            highlight: None,
+            bold: false,
+        };
+        let lines = pstree(&processes(), &HashMap::new(), &options);
+        assert_eq!(lines, vec!["sshd(2)───bash(3)───vim(6)"]);
+    }
+
+    #[test]
+    fn highlights_ancestors() {
+        assert_eq!(ancestors(&processes(), 6), HashSet::from([6, 3, 2, 1]));
+        let mut options = PstreeOptions {
+            root: Some(2),
+            pids: false,
+            highlight: Some(3),
+            bold: true,
+        };
+        let lines = pstree(&processes(), &HashMap::new(), &options);
+        assert_eq!(lines, vec!["\x1b[1msshd\x1b[0m───\x1b[1mbash\x1b[0m───vim"]);
+        options.bold = false;
+        let lines = pstree(&processes(), &HashMap::new(), &options);
+        assert_eq!(lines, vec!["sshd───bash───vim"]);
+    }
+}
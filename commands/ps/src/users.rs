@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// uid 到用户名的映射，来自 /etc/passwd
+#[derive(Debug, Clone, Default)]
+pub struct Users {
+    names: HashMap<u32, String>,
+}
+
+impl Users {
+    /// 读取 passwd 文件，读不到时所有用户都显示为 uid
+    pub fn read(passwd: &Path) -> Users {
+        let text = fs::read_to_string(passwd).unwrap_or_default();
+        Users::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Users {
+        let names = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let uid = fields.nth(1)?.parse().ok()?;
+                Some((uid, name.to_string()))
+            })
+            .collect();
+        Users { names }
+    }
+
+    pub fn name(&self, uid: u32) -> String {
+        self.names
+            .get(&uid)
+            .cloned()
+            .unwrap_or_else(|| uid.to_string())
+    }
+
+    /// 按用户名或数字查找 uid
+    pub fn uid(&self, user: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .find(|(_, name)| *name == user)
+            .map(|(uid, _)| *uid)
+            .or_else(|| user.parse().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_uids() {
+        let users = Users::parse(
+            "root:x:0:0:root:/root:/bin/bash\n\
+             alice:x:1000:1000::/home/alice:/bin/sh\n",
+        );
+        assert_eq!(users.name(0), "root");
+        assert_eq!(users.name(1000), "alice");
+        assert_eq!(users.name(4242), "4242");
+        assert_eq!(users.uid("alice"), Some(1000));
+        assert_eq!(users.uid("77"), Some(77));
+        assert_eq!(users.uid("bob"), None);
+    }
+}
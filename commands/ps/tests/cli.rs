@@ -0,0 +1,98 @@
+use ps::{MockSource, PsError, Users};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+type TestResult = Result<(), Box<dyn Error>>;
+
+const PROC: &str = "tests/inputs/proc";
+const PASSWD: &str = "tests/inputs/passwd";
+/// fixture 中 alice 在 pts/0 上的 bash
+const SHELL: i32 = 300;
+
+// --------------------------------------------------
+/// 在 fixture 上运行 ps，返回标准输出
+fn ps(args: &str) -> Result<String, PsError> {
+    // START 和 STIME 按本地时间显示
+    std::env::set_var("TZ", "UTC");
+    let source = MockSource::new(PROC, SHELL);
+    let users = Users::read(Path::new(PASSWD));
+    let args: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+    let mut out = vec![];
+    ps::run_with(&source, &users, &args, &mut out)?;
+    Ok(String::from_utf8(out).expect("输出不是 UTF-8"))
+}
+
+// --------------------------------------------------
+fn run(args: &str, expected_file: &str) -> TestResult {
+    let expected = fs::read_to_string(expected_file)?;
+    assert_eq!(ps(args)?, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn default_selection() -> TestResult {
+    run("", "tests/expected/default.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn full_format() -> TestResult {
+    run("-ef", "tests/expected/ef.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn bsd_user_format() -> TestResult {
+    run("aux", "tests/expected/aux.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn selects_and_sorts() -> TestResult {
+    run(
+        "-u alice -o pid,nlwp,rss,comm --sort -rss",
+        "tests/expected/user_sort.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn forest() -> TestResult {
+    run("-e --forest -o pid,ppid,args", "tests/expected/forest.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn pstree() -> TestResult {
+    run("--pstree --show-pids", "tests/expected/pstree.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn units() -> TestResult {
+    run("-eo pid,unit,cgroup", "tests/expected/units.out")?;
+    run(
+        "--unit worker -o pid,comm",
+        "tests/expected/unit_filter.out",
+    )?;
+    run("-e --group unit", "tests/expected/group_unit.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn threads() -> TestResult {
+    run("-L -p 410", "tests/expected/threads.out")?;
+    run(
+        "-T -p 410 -o pid,spid,comm,stat,psr,time",
+        "tests/expected/thread_columns.out",
+    )?;
+    run("-eLf", "tests/expected/threads_full.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn unknown_process() {
+    assert!(matches!(ps("--inspect 999"), Err(PsError::Io(_))));
+}